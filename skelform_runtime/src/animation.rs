@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::armature::{Bone, Vec2};
+use crate::easing::ease;
+use crate::utils::{lerp_angle, lerp_vec2};
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Interpolation {
+    Step,
+    #[default]
+    Linear,
+    Bezier,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Back,
+    Elastic,
+}
+
+/// how a keyframe transitions into the next one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Curve {
+    pub interp: Interpolation,
+
+    // control points of the bezier curve (x1, y1, x2, y2), with
+    // time and value normalized between this keyframe and the next
+    pub handles: [f32; 4],
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve {
+            interp: Interpolation::Linear,
+            handles: [0.42, 0., 0.58, 1.],
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+    pub frame: i32,
+    pub value: T,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+/// keyframes of a single bone, with each property animated separately
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct BoneTrack {
+    pub bone_id: i32,
+    pub pos: Vec<Keyframe<Vec2>>,
+    pub rot: Vec<Keyframe<f32>>,
+    pub scale: Vec<Keyframe<Vec2>>,
+
+    // names of the active attachment, held until the next key
+    #[serde(default)]
+    pub attachment: Vec<Keyframe<String>>,
+
+    // offsets of mesh vertices (see `Bone::deform`)
+    #[serde(default)]
+    pub deform: Vec<Keyframe<Vec<Vec2>>>,
+}
+
+/// a named clip
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Animation {
+    pub name: String,
+    pub fps: i32,
+    pub tracks: Vec<BoneTrack>,
+
+    // whole draw orders (see `Armature::draw_order`), held until the next key
+    #[serde(default)]
+    pub draw_order: Vec<Keyframe<Vec<i32>>>,
+}
+
+pub fn find_track(anim: &Animation, bone_id: i32) -> Option<&BoneTrack> {
+    anim.tracks.iter().find(|t| t.bone_id == bone_id)
+}
+
+/// last frame that has a keyframe on it
+pub fn last_frame(anim: &Animation) -> i32 {
+    let mut last = 0;
+    for t in &anim.tracks {
+        #[rustfmt::skip]
+        let frames = [
+            t.pos.last().map(|k| k.frame),
+            t.rot.last().map(|k| k.frame),
+            t.scale.last().map(|k| k.frame),
+            t.attachment.last().map(|k| k.frame),
+            t.deform.last().map(|k| k.frame),
+        ];
+        for f in frames.into_iter().flatten() {
+            last = i32::max(last, f);
+        }
+    }
+    if let Some(k) = anim.draw_order.last() {
+        last = i32::max(last, k.frame);
+    }
+    last
+}
+
+/// value of a property at this (possibly fractional) frame, interpolated
+/// between the surrounding keyframes with the earlier one's curve
+pub fn interpolate<T: Clone>(
+    keys: &[Keyframe<T>],
+    frame: f32,
+    lerp: fn(&T, &T, f32) -> T,
+) -> Option<T> {
+    if keys.is_empty() {
+        return None;
+    }
+
+    match keys.iter().position(|k| k.frame as f32 > frame) {
+        // before the first or after the last keyframe, so just hold it
+        Some(0) => Some(keys[0].value.clone()),
+        None => Some(keys[keys.len() - 1].value.clone()),
+
+        Some(i) => {
+            let prev = &keys[i - 1];
+            let next = &keys[i];
+            let t = (frame - prev.frame as f32) / (next.frame - prev.frame) as f32;
+            Some(lerp(&prev.value, &next.value, ease(&prev.curve, t)))
+        }
+    }
+}
+
+/// set bones to their animated pose at this frame.
+/// properties without keyframes are left untouched
+pub fn pose_armature(bones: &mut [Bone], anim: &Animation, frame: f32) {
+    for b in bones {
+        let track = match find_track(anim, b.id) {
+            Some(t) => t,
+            None => continue,
+        };
+        if let Some(pos) = interpolate(&track.pos, frame, lerp_vec2) {
+            b.pos = pos;
+        }
+        if let Some(rot) = interpolate(&track.rot, frame, lerp_angle) {
+            b.rot = rot;
+        }
+        if let Some(scale) = interpolate(&track.scale, frame, lerp_vec2) {
+            b.scale = scale;
+        }
+        if let Some(name) = interpolate(&track.attachment, frame, hold) {
+            b.attachment = name;
+        }
+        if let Some(deform) = interpolate(&track.deform, frame, |a, b, t| lerp_offsets(a, b, t)) {
+            b.deform = deform;
+        }
+    }
+}
+
+/// set the draw order to the animated one at this frame. each keyframe
+/// holds until the next one, regardless of its curve
+pub fn pose_draw_order(draw_order: &mut Vec<i32>, anim: &Animation, frame: f32) {
+    if let Some(order) = interpolate(&anim.draw_order, frame, hold) {
+        *draw_order = order;
+    }
+}
+
+/// "interpolation" for values that can't be blended
+fn hold<T: Clone>(prev: &T, _next: &T, _t: f32) -> T {
+    prev.clone()
+}
+
+/// blend each vertex's offset. keys with a different number of them
+/// (from a mesh that was changed since) are held instead
+fn lerp_offsets(prev: &[Vec2], next: &[Vec2], t: f32) -> Vec<Vec2> {
+    if prev.len() != next.len() {
+        return prev.to_vec();
+    }
+    prev.iter()
+        .zip(next)
+        .map(|(a, b)| lerp_vec2(a, b, t))
+        .collect()
+}
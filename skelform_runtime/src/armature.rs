@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+use crate::transform::Transform2D;
+use crate::utils;
+
+#[repr(C)]
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub fn add(&self, other: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+
+    pub fn sub(&self, other: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+
+    pub fn scale(&self, s: f32) -> Vec2 {
+        Vec2 {
+            x: self.x * s,
+            y: self.y * s,
+        }
+    }
+
+    pub fn dot(&self, other: &Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// same direction with a length of 1, or along x for a zero vector
+    pub fn normalize(&self) -> Vec2 {
+        let l = self.length();
+        if l == 0. {
+            return Vec2 { x: 1., y: 0. };
+        }
+        self.scale(1. / l)
+    }
+
+    /// counter-clockwise around the origin, by `rot` radians
+    pub fn rotate(&self, rot: f32) -> Vec2 {
+        utils::rotate(self, rot)
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Armature {
+    pub bones: Vec<Bone>,
+    #[serde(default)]
+    pub animations: Vec<Animation>,
+
+    // ids of bones in the order they're drawn, from back to front.
+    // kept apart from `bones`, so reparenting doesn't change it
+    #[serde(default)]
+    pub draw_order: Vec<i32>,
+
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+}
+
+/// a named set of attachments to show at once, like an outfit. bones it
+/// leaves out use its base skin's, or their own `attachment` without one
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Skin {
+    pub name: String,
+    pub base: String, // name of the skin to fall back to, if any
+    pub attachments: Vec<SkinAttachment>,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkinAttachment {
+    pub bone_id: i32,
+    pub attachment: String, // name of one of the bone's attachments
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoneTexture {
+    pub idx: usize, // index relative to the project's textures
+
+    // point of the image that sits on the bone (and that it rotates around),
+    // from (0, 0) at the bottom left to (1, 1) at the top right
+    pub pivot: Vec2,
+
+    // moves the image away from the bone, in the bone's space
+    // (see `image_transform`)
+    pub offset: Vec2,
+
+    // drawn as a plain quad if it has no triangles
+    pub mesh: Mesh,
+}
+
+impl Default for BoneTexture {
+    fn default() -> Self {
+        BoneTexture {
+            idx: 0,
+            pivot: Vec2 { x: 0.5, y: 0.5 },
+            offset: Vec2::default(),
+            mesh: Mesh::default(),
+        }
+    }
+}
+
+/// a texture split into triangles, so it can be bent by moving its
+/// vertices rather than only being moved around as a whole
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mesh {
+    // where each vertex is on the image, from (0, 0) at the bottom
+    // left to (1, 1) at the top right, like `BoneTexture::pivot`
+    pub vertices: Vec<Vec2>,
+    pub outline: usize,      // how many of the first vertices go around the edge
+    pub triangles: Vec<u32>, // indices of `vertices`, three per triangle
+
+    // bones the vertices can be weighted to, and the weights of each vertex
+    // (in the same order as `vertices`). vertices without any weights only
+    // follow the bone showing the mesh
+    pub bones: Vec<MeshBone>,
+    pub weights: Vec<Vec<VertexWeight>>,
+}
+
+/// a bone that a mesh's vertices can follow
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeshBone {
+    pub bone_id: i32,
+
+    // transform from the space of the bone showing the mesh to this bone's,
+    // as they were placed when it was bound. weighted vertices follow
+    // however it has moved since
+    pub bind: Transform2D,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VertexWeight {
+    pub bone_id: i32, // one of the mesh's `bones`
+    pub weight: f32,
+}
+
+/// one of several images a bone can swap between, like mouth shapes or
+/// hand poses. its rotation and scale are on top of the bone's
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attachment {
+    pub name: String,
+    pub tex: BoneTexture,
+    pub rot: f32,
+    pub scale: Vec2,
+}
+
+impl Default for Attachment {
+    fn default() -> Self {
+        Attachment {
+            name: String::new(),
+            tex: BoneTexture::default(),
+            rot: 0.,
+            scale: Vec2 { x: 1., y: 1. },
+        }
+    }
+}
+
+/// length of new bones, in their own space
+pub const DEFAULT_BONE_LENGTH: f32 = 0.2;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bone {
+    pub name: String,
+    pub parent_id: i32,
+    pub pos: Vec2,
+    pub rot: f32,
+    pub scale: Vec2,
+    pub id: i32,
+    pub tex: BoneTexture,
+
+    // how far the bone reaches along its x axis. only
+    // used for showing it, since children are placed freely
+    #[serde(default = "default_bone_length")]
+    pub length: f32,
+
+    // images the bone can show instead of `tex`. the one named
+    // by `attachment` is shown, or `tex` if there's none by that name
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub attachment: String,
+
+    // how far each vertex of the shown mesh is moved from where the mesh
+    // puts it, in image space (see `image_transform`). vertices past the
+    // end aren't moved
+    #[serde(default)]
+    pub deform: Vec<Vec2>,
+}
+
+fn default_bone_length() -> f32 {
+    DEFAULT_BONE_LENGTH
+}
+
+pub fn find_bone(bones: &[Bone], id: i32) -> Option<&Bone> {
+    bones.iter().find(|b| b.id == id)
+}
+
+pub fn find_skin<'a>(skins: &'a [Skin], name: &str) -> Option<&'a Skin> {
+    skins.iter().find(|s| s.name == name)
+}
+
+/// name of the attachment a bone shows with a skin, looking through the
+/// skin's bases for bones it leaves out, and finally the bone itself
+pub fn skin_attachment<'a>(skins: &'a [Skin], skin: &str, bone: &'a Bone) -> &'a str {
+    let mut name = skin;
+
+    // bounded, in case bases loop back around
+    for _ in 0..skins.len() {
+        let s = match find_skin(skins, name) {
+            Some(s) => s,
+            None => break,
+        };
+        if let Some(a) = s.attachments.iter().find(|a| a.bone_id == bone.id) {
+            return &a.attachment;
+        }
+        name = &s.base;
+    }
+    &bone.attachment
+}
+
+/// show a skin on bones, by setting their active attachments
+pub fn apply_skin(bones: &mut [Bone], skins: &[Skin], skin: &str) {
+    for b in bones {
+        b.attachment = skin_attachment(skins, skin, b).to_string();
+    }
+}
+
+/// attachment the bone is showing, if any
+pub fn active_attachment(bone: &Bone) -> Option<&Attachment> {
+    if bone.attachment.is_empty() {
+        return None;
+    }
+    bone.attachments.iter().find(|a| a.name == bone.attachment)
+}
+
+/// texture the bone is showing, either its active attachment's or its own
+pub fn bone_texture(bone: &Bone) -> &BoneTexture {
+    match active_attachment(bone) {
+        Some(a) => &a.tex,
+        None => &bone.tex,
+    }
+}
+
+pub fn bone_texture_mut(bone: &mut Bone) -> &mut BoneTexture {
+    let name = &bone.attachment;
+    match bone.attachments.iter_mut().find(|a| a.name == *name) {
+        Some(a) if !name.is_empty() => &mut a.tex,
+        _ => &mut bone.tex,
+    }
+}
+
+/// transform from the space of the image a bone shows to the bone's own.
+/// it moves the image by its offset, then applies an attachment's
+/// rotation and scale around the pivot
+pub fn image_transform(bone: &Bone) -> Transform2D {
+    match active_attachment(bone) {
+        Some(a) => Transform2D::new(&a.tex.offset, a.rot, &a.scale),
+        None => Transform2D::from_translation(&bone.tex.offset),
+    }
+}
+
+/// transforms from the space of the bone at `idx` to world space, one for
+/// each bone its mesh is bound to (in the same order as `Mesh::bones`),
+/// that move the mesh along with that bone. bones that are gone leave
+/// the mesh with the one at `idx`
+pub fn skin_transforms(
+    mesh: &Mesh,
+    bones: &[Bone],
+    world: &[Transform2D],
+    idx: usize,
+) -> Vec<Transform2D> {
+    let own = world.get(idx).copied().unwrap_or_default();
+    mesh.bones
+        .iter()
+        .map(|mb| match bones.iter().position(|b| b.id == mb.bone_id) {
+            Some(i) if i < world.len() => world[i].compose(&mb.bind),
+            _ => own,
+        })
+        .collect()
+}
+
+/// transform that places a mesh vertex, blending `skin` (from
+/// `skin_transforms`, or those composed with others) by the vertex's
+/// weights. this is linear blend skinning. vertices without weights,
+/// or only weights to bones missing from `skin`, use `transform`
+pub fn vertex_transform(
+    mesh: &Mesh,
+    vertex: usize,
+    transform: &Transform2D,
+    skin: &[Transform2D],
+) -> Transform2D {
+    let parts = |t: &Transform2D| [t.a, t.b, t.c, t.d, t.x, t.y];
+    let mut sum = [0.; 6];
+    let mut total = 0.;
+    for w in mesh.weights.get(vertex).map(|w| &w[..]).unwrap_or_default() {
+        let bone = mesh.bones.iter().position(|b| b.bone_id == w.bone_id);
+        let t = match bone.and_then(|i| skin.get(i)) {
+            Some(t) => t,
+            None => continue,
+        };
+        for (s, p) in sum.iter_mut().zip(parts(t)) {
+            *s += p * w.weight;
+        }
+        total += w.weight;
+    }
+    if total <= 0. {
+        return *transform;
+    }
+
+    // weights don't have to add up to 1
+    let [a, b, c, d, x, y] = sum.map(|s| s / total);
+    Transform2D { a, b, c, d, x, y }
+}
+
+/// indices of bones in the order they're drawn, from back to front. bones
+/// missing from `draw_order` (such as new ones) go in front of the rest,
+/// while ids of bones that don't exist anymore are skipped
+pub fn draw_order(armature: &Armature) -> Vec<usize> {
+    bone_order(&armature.bones, &armature.draw_order)
+}
+
+/// same as `draw_order`, for bones and an order that may
+/// be posed separately from the armature they're from
+pub fn bone_order(bones: &[Bone], draw_order: &[i32]) -> Vec<usize> {
+    let mut order: Vec<usize> = vec![];
+    for id in draw_order {
+        if let Some(idx) = bones.iter().position(|b| b.id == *id) {
+            if !order.contains(&idx) {
+                order.push(idx);
+            }
+        }
+    }
+    for idx in 0..bones.len() {
+        if !order.contains(&idx) {
+            order.push(idx);
+        }
+    }
+    order
+}
+
+/// transform from a bone's space to its parent's
+pub fn local_transform(bone: &Bone) -> Transform2D {
+    Transform2D::new(&bone.pos, bone.rot, &bone.scale)
+}
+
+/// get transforms from each bone's space to world space,
+/// in the same order as `bones`.
+///
+/// parents don't need to come before their children. bones that end up
+/// being their own ancestor are treated as roots where the cycle closes
+pub fn world_transforms(bones: &[Bone]) -> Vec<Transform2D> {
+    let mut indices = HashMap::new();
+    for (i, b) in bones.iter().enumerate() {
+        indices.insert(b.id, i);
+    }
+
+    let mut world: Vec<Option<Transform2D>> = vec![None; bones.len()];
+    let mut visiting = vec![false; bones.len()];
+    for i in 0..bones.len() {
+        resolve(bones, &indices, i, &mut world, &mut visiting);
+    }
+
+    world.into_iter().map(|b| b.unwrap()).collect()
+}
+
+/// get world transform of a bone, resolving its parents first if needed
+fn resolve(
+    bones: &[Bone],
+    indices: &HashMap<i32, usize>,
+    idx: usize,
+    world: &mut Vec<Option<Transform2D>>,
+    visiting: &mut Vec<bool>,
+) -> Transform2D {
+    if let Some(wt) = world[idx] {
+        return wt;
+    }
+
+    let mut parent = Transform2D::IDENTITY;
+
+    visiting[idx] = true;
+    if let Some(&p) = indices.get(&bones[idx].parent_id) {
+        // don't follow a parent that's already being resolved, as that's a cycle
+        if !visiting[p] {
+            parent = resolve(bones, indices, p, world, visiting);
+        }
+    }
+    visiting[idx] = false;
+
+    let wt = parent.compose(&local_transform(&bones[idx]));
+    world[idx] = Some(wt);
+    wt
+}
+
+/// ids of bones that are their own ancestor
+pub fn find_cycles(bones: &[Bone]) -> Vec<i32> {
+    let mut cycles = vec![];
+    for b in bones {
+        let mut parent_id = b.parent_id;
+
+        // any chain longer than there are bones must loop
+        for _ in 0..bones.len() {
+            if parent_id == b.id {
+                cycles.push(b.id);
+                break;
+            }
+            match find_bone(bones, parent_id) {
+                Some(p) => parent_id = p.parent_id,
+                None => break,
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bone(id: i32, parent_id: i32, x: f32) -> Bone {
+        Bone {
+            id,
+            parent_id,
+            pos: Vec2 { x, y: 0. },
+            scale: Vec2 { x: 1., y: 1. },
+            ..Default::default()
+        }
+    }
+
+    fn x_of(world: &Transform2D) -> f32 {
+        world.translation().x
+    }
+
+    #[test]
+    fn children_before_parents() {
+        let bones = vec![bone(2, 1, 1.), bone(1, 0, 2.), bone(0, -1, 4.)];
+        let world = world_transforms(&bones);
+        assert_eq!(x_of(&world[0]), 7.);
+        assert_eq!(x_of(&world[1]), 6.);
+        assert_eq!(x_of(&world[2]), 4.);
+    }
+
+    #[test]
+    fn deep_chain() {
+        let count = 1000;
+        let mut bones: Vec<Bone> = (0..count).map(|i| bone(i, i - 1, 1.)).collect();
+        bones.reverse();
+        let world = world_transforms(&bones);
+        assert_eq!(x_of(&world[0]), count as f32);
+        assert_eq!(x_of(&world[count as usize - 1]), 1.);
+    }
+
+    #[test]
+    fn missing_parent_is_a_root() {
+        let bones = vec![bone(0, 42, 3.)];
+        let world = world_transforms(&bones);
+        assert_eq!(x_of(&world[0]), 3.);
+        assert!(find_cycles(&bones).is_empty());
+    }
+
+    #[test]
+    fn cycles_are_found_and_resolved() {
+        // 0 and 1 are each other's parent, 2 is its own
+        let bones = vec![bone(0, 1, 1.), bone(1, 0, 2.), bone(2, 2, 5.)];
+        let mut cycles = find_cycles(&bones);
+        cycles.sort();
+        assert_eq!(cycles, vec![0, 1, 2]);
+
+        // the cycle is cut where it closes, so one of the pair ends up a root
+        let world = world_transforms(&bones);
+        assert_eq!(world.len(), 3);
+        let (a, b) = (x_of(&world[0]), x_of(&world[1]));
+        assert!((a == 3. && b == 2.) || (a == 1. && b == 3.));
+        assert_eq!(x_of(&world[2]), 5.);
+    }
+}
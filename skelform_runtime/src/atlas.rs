@@ -0,0 +1,369 @@
+use serde::{Deserialize, Serialize};
+
+use crate::armature::{bone_texture, Bone, BoneTexture, Vec2};
+
+/// size of an image pixel in a bone's space
+pub const PIXEL_SIZE: f32 = 0.002;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtlasOptions {
+    pub max_size: u32, // of a page, in either direction
+    pub padding: u32,  // empty pixels around each image
+    pub trim: bool,    // cut off fully transparent borders
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        AtlasOptions {
+            max_size: 2048,
+            padding: 2,
+            trim: true,
+        }
+    }
+}
+
+/// where an image ended up in the atlas
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    pub page: usize,
+
+    // trimmed image's rect in the page, in pixels
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+
+    // where the trimmed rect sits in the original image,
+    // so it can still be drawn at the original size
+    pub offset_x: u32,
+    pub offset_y: u32,
+    pub original_width: u32,
+    pub original_height: u32,
+
+    pub uv: UvRect,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UvRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+#[derive(Clone, Default)]
+pub struct AtlasPage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// images packed together. `regions` are in the same order as the packed
+/// images, so a bone's texture index also points to its region
+#[derive(Clone, Default)]
+pub struct Atlas {
+    pub pages: Vec<AtlasPage>,
+    pub regions: Vec<AtlasRegion>,
+}
+
+/// an image to be packed, as rgba rows
+pub struct AtlasImage<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: &'a [u8],
+}
+
+/// pack images into as few power-of-two pages as possible. images bigger
+/// than `max_size` get a page of their own, big enough to fit them
+pub fn pack(images: &[AtlasImage], options: &AtlasOptions) -> Atlas {
+    let pad = options.padding;
+
+    // trimmed rect of each image, as (x, y, width, height)
+    let rects: Vec<(u32, u32, u32, u32)> = images
+        .iter()
+        .map(|img| {
+            if options.trim {
+                opaque_bounds(img)
+            } else {
+                (0, 0, img.width, img.height)
+            }
+        })
+        .collect();
+
+    // tallest first, so shelves waste less space
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by(|a, b| rects[*b].3.cmp(&rects[*a].3));
+
+    // shelf packing: fill rows left to right, starting a new
+    // row when one is full and a new page when that one is
+    let mut placed: Vec<(usize, u32, u32)> = vec![(0, 0, 0); images.len()];
+    let mut page_used: Vec<(u32, u32)> = vec![]; // extents of each page
+    let mut page = usize::MAX;
+    let (mut cursor_x, mut cursor_y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = (rects[i].2 + pad * 2, rects[i].3 + pad * 2);
+
+        if page != usize::MAX && cursor_x + w > options.max_size {
+            cursor_x = 0;
+            cursor_y += shelf_height;
+            shelf_height = 0;
+        }
+        if page == usize::MAX || cursor_y + h > options.max_size {
+            page = page_used.len();
+            page_used.push((0, 0));
+            (cursor_x, cursor_y, shelf_height) = (0, 0, 0);
+        }
+
+        placed[i] = (page, cursor_x + pad, cursor_y + pad);
+        let used = &mut page_used[page];
+        used.0 = u32::max(used.0, cursor_x + w);
+        used.1 = u32::max(used.1, cursor_y + h);
+
+        cursor_x += w;
+        shelf_height = u32::max(shelf_height, h);
+    }
+
+    let mut pages: Vec<AtlasPage> = page_used
+        .iter()
+        .map(|(w, h)| {
+            let width = u32::max(*w, 1).next_power_of_two();
+            let height = u32::max(*h, 1).next_power_of_two();
+            AtlasPage {
+                width,
+                height,
+                rgba: vec![0; (width * height * 4) as usize],
+            }
+        })
+        .collect();
+
+    let mut regions = vec![];
+    for (i, img) in images.iter().enumerate() {
+        let (page, x, y) = placed[i];
+        let (ox, oy, w, h) = rects[i];
+        let p = &mut pages[page];
+
+        // copy row by row
+        for row in 0..h {
+            let src = (((oy + row) * img.width + ox) * 4) as usize;
+            let dst = (((y + row) * p.width + x) * 4) as usize;
+            let len = (w * 4) as usize;
+            p.rgba[dst..dst + len].copy_from_slice(&img.rgba[src..src + len]);
+        }
+
+        regions.push(AtlasRegion {
+            page,
+            x,
+            y,
+            width: w,
+            height: h,
+            offset_x: ox,
+            offset_y: oy,
+            original_width: img.width,
+            original_height: img.height,
+            uv: UvRect {
+                min: Vec2 {
+                    x: x as f32 / p.width as f32,
+                    y: y as f32 / p.height as f32,
+                },
+                max: Vec2 {
+                    x: (x + w) as f32 / p.width as f32,
+                    y: (y + h) as f32 / p.height as f32,
+                },
+            },
+        });
+    }
+
+    Atlas { pages, regions }
+}
+
+/// smallest rect holding all of an image's non-transparent pixels.
+/// fully transparent images become empty
+fn opaque_bounds(img: &AtlasImage) -> (u32, u32, u32, u32) {
+    let (mut min_x, mut min_y) = (u32::MAX, u32::MAX);
+    let (mut max_x, mut max_y) = (0, 0);
+    for y in 0..img.height {
+        for x in 0..img.width {
+            if img.rgba[((y * img.width + x) * 4 + 3) as usize] == 0 {
+                continue;
+            }
+            min_x = u32::min(min_x, x);
+            min_y = u32::min(min_y, y);
+            max_x = u32::max(max_x, x + 1);
+            max_y = u32::max(max_y, y + 1);
+        }
+    }
+    if min_x == u32::MAX {
+        return (0, 0, 0, 0);
+    }
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+impl Atlas {
+    /// flip pages upside down, along with all regions. for converting
+    /// between images stored bottom row first (like OpenGL) and top first
+    pub fn flip_vertically(&mut self) {
+        for p in &mut self.pages {
+            let row = (p.width * 4) as usize;
+            let mut flipped = Vec::with_capacity(p.rgba.len());
+            for r in p.rgba.chunks(row).rev() {
+                flipped.extend_from_slice(r);
+            }
+            p.rgba = flipped;
+        }
+        for r in &mut self.regions {
+            r.y = self.pages[r.page].height - r.y - r.height;
+            flip_region(r);
+        }
+    }
+}
+
+/// count a region's rows (apart from its place in the page)
+/// from the other end of the image
+fn flip_region(r: &mut AtlasRegion) {
+    r.offset_y = r.original_height - r.offset_y - r.height;
+    (r.uv.min.y, r.uv.max.y) = (1. - r.uv.max.y, 1. - r.uv.min.y);
+}
+
+/// region of the texture a bone shows, if any
+pub fn bone_region<'a>(atlas: &'a Atlas, bone: &Bone) -> Option<&'a AtlasRegion> {
+    atlas.regions.get(bone_texture(bone).idx)
+}
+
+/// rect a region covers in image space (see `image_transform`), as
+/// (left, bottom, right, top), with the texture's pivot on the origin.
+/// trimmed borders are left out of the rect but still count towards
+/// where the pivot is. for regions with rows counted from the bottom,
+/// as `pack` gives them. exported ones use `sprite_rect_top_first`
+pub fn sprite_rect(region: &AtlasRegion, tex: &BoneTexture) -> [f32; 4] {
+    let pivot_x = tex.pivot.x * region.original_width as f32;
+    let pivot_y = tex.pivot.y * region.original_height as f32;
+    let left = (region.offset_x as f32 - pivot_x) * PIXEL_SIZE;
+    let bottom = (region.offset_y as f32 - pivot_y) * PIXEL_SIZE;
+    [
+        left,
+        bottom,
+        left + region.width as f32 * PIXEL_SIZE,
+        bottom + region.height as f32 * PIXEL_SIZE,
+    ]
+}
+
+/// where a mesh vertex (see `Mesh::vertices`) is in image space,
+/// with the texture's pivot on the origin
+pub fn mesh_point(region: &AtlasRegion, tex: &BoneTexture, vertex: &Vec2) -> Vec2 {
+    Vec2 {
+        x: (vertex.x - tex.pivot.x) * region.original_width as f32 * PIXEL_SIZE,
+        y: (vertex.y - tex.pivot.y) * region.original_height as f32 * PIXEL_SIZE,
+    }
+}
+
+/// texture coordinates of a mesh vertex in the atlas. vertices on
+/// trimmed borders are clamped to the region. for regions with rows
+/// counted from the bottom, as `pack` gives them. exported ones use
+/// `mesh_uv_top_first`
+pub fn mesh_uv(region: &AtlasRegion, vertex: &Vec2) -> Vec2 {
+    let (min, max) = (&region.uv.min, &region.uv.max);
+    let along = |v: f32, original: u32, offset: u32, size: u32| {
+        if size == 0 {
+            return 0.;
+        }
+        ((v * original as f32 - offset as f32) / size as f32).clamp(0., 1.)
+    };
+    let x = along(
+        vertex.x,
+        region.original_width,
+        region.offset_x,
+        region.width,
+    );
+    let y = along(
+        vertex.y,
+        region.original_height,
+        region.offset_y,
+        region.height,
+    );
+    Vec2 {
+        x: min.x + (max.x - min.x) * x,
+        y: min.y + (max.y - min.y) * y,
+    }
+}
+
+/// `sprite_rect` for regions with rows counted from the top, such as
+/// exported ones (see `Atlas::flip_vertically`)
+pub fn sprite_rect_top_first(region: &AtlasRegion, tex: &BoneTexture) -> [f32; 4] {
+    let mut r = region.clone();
+    flip_region(&mut r);
+    sprite_rect(&r, tex)
+}
+
+/// `mesh_uv` for regions with rows counted from the top, such as exported
+/// ones (see `Atlas::flip_vertically`). coordinates are top first as well
+pub fn mesh_uv_top_first(region: &AtlasRegion, vertex: &Vec2) -> Vec2 {
+    let mut r = region.clone();
+    flip_region(&mut r);
+    let uv = mesh_uv(&r, vertex);
+    Vec2 {
+        x: uv.x,
+        y: 1. - uv.y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    // packed and flipped, along with the same atlas left as packed
+    fn packed_and_flipped() -> (Atlas, Atlas) {
+        // 4x8 image with only a 2x3 block opaque, off-center in both
+        // directions, so trimming cuts off more at one end than the other
+        let (width, height) = (4, 8);
+        let mut rgba = vec![0; (width * height * 4) as usize];
+        for y in 1..4 {
+            for x in 1..3 {
+                rgba[((y * width + x) * 4 + 3) as usize] = 255;
+            }
+        }
+        let images = [AtlasImage {
+            width,
+            height,
+            rgba: &rgba,
+        }];
+        let atlas = pack(&images, &AtlasOptions::default());
+        let mut flipped = atlas.clone();
+        flipped.flip_vertically();
+        (atlas, flipped)
+    }
+
+    #[test]
+    fn sprite_rect_top_first_matches_unflipped() {
+        let (atlas, flipped) = packed_and_flipped();
+        let tex = BoneTexture {
+            pivot: Vec2 { x: 0.5, y: 0.25 },
+            ..Default::default()
+        };
+        let expected = sprite_rect(&atlas.regions[0], &tex);
+        let rect = sprite_rect_top_first(&flipped.regions[0], &tex);
+        for (a, b) in rect.iter().zip(&expected) {
+            assert!(close(*a, *b), "{:?} != {:?}", rect, expected);
+        }
+    }
+
+    #[test]
+    fn mesh_uv_top_first_samples_the_same_pixel() {
+        let (atlas, flipped) = packed_and_flipped();
+        let vertices = [
+            Vec2 { x: 0.25, y: 0.125 },
+            Vec2 { x: 0.5, y: 0.3 },
+            Vec2 { x: 0.75, y: 0.5 },
+        ];
+        for v in &vertices {
+            let expected = mesh_uv(&atlas.regions[0], v);
+            let uv = mesh_uv_top_first(&flipped.regions[0], v);
+
+            // the flipped page's top row was the unflipped one's bottom
+            assert!(close(uv.x, expected.x));
+            assert!(close(uv.y, 1. - expected.y));
+        }
+    }
+}
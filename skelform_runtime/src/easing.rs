@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+use crate::animation::{Curve, Interpolation};
+
+/// map linear progress between two keyframes (0 to 1) onto the curve.
+/// the result may go beyond 0 and 1 for overshooting curves
+pub fn ease(curve: &Curve, t: f32) -> f32 {
+    match curve.interp {
+        // hold until the next keyframe
+        Interpolation::Step => 0.,
+        Interpolation::Linear => t,
+        Interpolation::Bezier => cubic_bezier(&curve.handles, t),
+        Interpolation::EaseIn => t * t * t,
+        Interpolation::EaseOut => 1. - (1. - t).powi(3),
+        Interpolation::EaseInOut => {
+            if t < 0.5 {
+                4. * t * t * t
+            } else {
+                1. - (-2. * t + 2.).powi(3) / 2.
+            }
+        }
+        Interpolation::Back => {
+            let c1 = 1.70158;
+            let c3 = c1 + 1.;
+            1. + c3 * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+        }
+        Interpolation::Elastic => {
+            if t <= 0. || t >= 1. {
+                return t;
+            }
+            let c4 = (2. * PI) / 3.;
+            f32::powf(2., -10. * t) * ((t * 10. - 0.75) * c4).sin() + 1.
+        }
+    }
+}
+
+/// evaluate a cubic bezier going from (0, 0) to (1, 1) at time `t`.
+/// handles are clamped horizontally, so time never goes backwards
+pub fn cubic_bezier(handles: &[f32; 4], t: f32) -> f32 {
+    let x1 = handles[0].clamp(0., 1.);
+    let x2 = handles[2].clamp(0., 1.);
+
+    #[rustfmt::skip]
+    let bezier = |p1: f32, p2: f32, s: f32| {
+        3. * (1. - s).powi(2) * s * p1
+        + 3. * (1. - s) * s * s * p2
+        + s * s * s
+    };
+
+    // find where the curve is at this time (bisection is
+    // plenty fast for a handful of bones)
+    let mut lo = 0.;
+    let mut hi = 1.;
+    let mut s = t;
+    for _ in 0..24 {
+        s = (lo + hi) / 2.;
+        if bezier(x1, x2, s) < t {
+            lo = s;
+        } else {
+            hi = s;
+        }
+    }
+
+    bezier(handles[1], handles[3], s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_start_at_0_and_end_at_1() {
+        let interps = [
+            Interpolation::Linear,
+            Interpolation::Bezier,
+            Interpolation::EaseIn,
+            Interpolation::EaseOut,
+            Interpolation::EaseInOut,
+            Interpolation::Back,
+            Interpolation::Elastic,
+        ];
+        for interp in interps {
+            let curve = Curve {
+                interp,
+                ..Default::default()
+            };
+            assert!(ease(&curve, 0.).abs() < 1e-4);
+            assert!((ease(&curve, 1.) - 1.).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn step_holds_until_the_next_key() {
+        let curve = Curve {
+            interp: Interpolation::Step,
+            ..Default::default()
+        };
+        assert_eq!(ease(&curve, 0.), 0.);
+        assert_eq!(ease(&curve, 0.99), 0.);
+    }
+
+    #[test]
+    fn bezier_never_goes_backwards() {
+        // including handles dragged past the ends horizontally
+        for handles in [
+            [0.42, 0., 0.58, 1.],
+            [0., 1., 1., 0.],
+            [-0.5, 0.2, 1.5, 0.8],
+        ] {
+            let mut last = cubic_bezier(&handles, 0.);
+            for i in 1..=100 {
+                let value = cubic_bezier(&handles, i as f32 / 100.);
+                assert!(value >= last - 1e-5, "{:?} went back at {}", handles, i);
+                last = value;
+            }
+        }
+    }
+}
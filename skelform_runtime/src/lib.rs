@@ -0,0 +1,35 @@
+//! Loads SkelForm projects and evaluates their armatures, without
+//! any windowing or GPU dependencies. The editor uses this same
+//! code, so what's seen in SkelForm is exactly what ships.
+
+pub mod animation;
+pub mod armature;
+pub mod atlas;
+pub mod easing;
+pub mod project;
+pub mod transform;
+pub mod utils;
+
+pub use animation::{Animation, BoneTrack, Curve, Interpolation, Keyframe};
+pub use armature::{
+    Armature, Attachment, Bone, BoneTexture, Mesh, MeshBone, Skin, SkinAttachment, Vec2,
+    VertexWeight,
+};
+pub use atlas::{Atlas, AtlasOptions, AtlasRegion, UvRect};
+pub use transform::Transform2D;
+
+/// pose the armature with an animation at `time` (in seconds), and get
+/// world transforms of every bone, in the same order as `armature.bones`
+pub fn sample(armature: &Armature, anim: &Animation, time: f32) -> Vec<Transform2D> {
+    let mut bones = armature.bones.clone();
+    animation::pose_armature(&mut bones, anim, time * anim.fps as f32);
+    armature::world_transforms(&bones)
+}
+
+/// indices of bones in the order they should be drawn (back to front)
+/// with an animation at `time`, in seconds
+pub fn sample_draw_order(armature: &Armature, anim: &Animation, time: f32) -> Vec<usize> {
+    let mut order = armature.draw_order.clone();
+    animation::pose_draw_order(&mut order, anim, time * anim.fps as f32);
+    armature::bone_order(&armature.bones, &order)
+}
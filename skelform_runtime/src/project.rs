@@ -0,0 +1,256 @@
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::armature::{find_cycles, Armature, Bone, Vec2};
+use crate::atlas::{AtlasOptions, AtlasRegion};
+
+/// bump this whenever the saved layout changes, so older
+/// editors refuse files they can't understand
+pub const VERSION: u32 = 8;
+
+pub const EXTENSION: &str = "skf";
+pub const EXPORT_EXTENSION: &str = "skfe";
+
+/// name of the armature description inside the archive
+const ARMATURE_FILE: &str = "armature.json";
+
+/// editor view, saved along with the project
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Camera {
+    pub pos: Vec2,
+    pub zoom: f32,
+}
+
+/// reference to an image bundled in the archive
+#[derive(Serialize, Deserialize)]
+pub struct ProjectTexture {
+    pub name: String,
+    pub file: String, // path inside the archive
+}
+
+/// everything needed to restore an editing session, minus
+/// the images themselves which live next to it in the archive
+#[derive(Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub armature: Armature,
+    pub textures: Vec<ProjectTexture>,
+    pub camera: Camera,
+    #[serde(default)]
+    pub atlas: AtlasOptions,
+}
+
+/// image file as it was imported, still encoded
+#[derive(Clone, Default)]
+pub struct Image {
+    pub name: String,
+    pub file: Vec<u8>,
+}
+
+/// contents of a project archive. bones' texture indices point to `images`
+#[derive(Default)]
+pub struct ProjectFile {
+    pub armature: Armature,
+    pub camera: Camera,
+    pub atlas: AtlasOptions,
+    pub images: Vec<Image>,
+
+    // problems that didn't stop it from being read, like missing images
+    pub warnings: Vec<String>,
+}
+
+pub fn write(path: &str, project: &ProjectFile) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    // bundle original images, prefixed with their index so
+    // identically-named ones don't overwrite each other
+    let mut textures: Vec<ProjectTexture> = vec![];
+    for (i, img) in project.images.iter().enumerate() {
+        let file = format!("textures/{}_{}", i, img.name);
+        zip.start_file(file.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(&img.file).map_err(|e| e.to_string())?;
+        textures.push(ProjectTexture {
+            name: img.name.clone(),
+            file,
+        });
+    }
+
+    let description = Project {
+        version: VERSION,
+        armature: project.armature.clone(),
+        textures,
+        camera: project.camera.clone(),
+        atlas: project.atlas.clone(),
+    };
+    let json = serde_json::to_string(&description).map_err(|e| e.to_string())?;
+    zip.start_file(ARMATURE_FILE, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// read a project archive. images missing from it are left out (and listed
+/// in `warnings`), with any bones using them re-pointed to no texture
+pub fn read(path: &str) -> Result<ProjectFile, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let mut json = String::new();
+    zip.by_name(ARMATURE_FILE)
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+    let mut description: Project = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+    if description.version > VERSION {
+        return Err(format!(
+            "project version {} is newer than supported version {}",
+            description.version, VERSION
+        ));
+    }
+
+    let cycles = find_cycles(&description.armature.bones);
+    if !cycles.is_empty() {
+        return Err(format!("bones {:?} are their own ancestors", cycles));
+    }
+
+    // gather images, remembering where each one ended up
+    let mut images = vec![];
+    let mut warnings = vec![];
+    let mut new_idx = vec![usize::MAX; description.textures.len()];
+    for (i, t) in description.textures.iter().enumerate() {
+        let mut bytes = vec![];
+        let read = match zip.by_name(&t.file) {
+            Ok(mut f) => f.read_to_end(&mut bytes).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match read {
+            Ok(_) => {
+                new_idx[i] = images.len();
+                images.push(Image {
+                    name: t.name.clone(),
+                    file: bytes,
+                });
+            }
+            Err(e) => warnings.push(format!("Could not read texture '{}': {}", t.file, e)),
+        }
+    }
+    repoint_textures(&mut description.armature.bones, &new_idx);
+
+    Ok(ProjectFile {
+        armature: description.armature,
+        camera: description.camera,
+        atlas: description.atlas,
+        images,
+        warnings,
+    })
+}
+
+/// update bones' texture indices after textures were removed or
+/// reordered, with `new_idx[old]` being where each one went
+pub fn repoint_textures(bones: &mut [Bone], new_idx: &[usize]) {
+    for b in bones {
+        let textures =
+            std::iter::once(&mut b.tex).chain(b.attachments.iter_mut().map(|a| &mut a.tex));
+        for tex in textures {
+            if tex.idx < new_idx.len() {
+                tex.idx = new_idx[tex.idx];
+            } else {
+                tex.idx = usize::MAX;
+            }
+        }
+    }
+}
+
+/// description of an exported armature. unlike a project, it has no editor
+/// state, and textures are packed into atlas pages instead of kept as is.
+/// pages and regions are top row first, like png images, so regions go with
+/// `sprite_rect_top_first` and `mesh_uv_top_first`
+#[derive(Serialize, Deserialize)]
+pub struct Export {
+    pub version: u32,
+    pub armature: Armature,
+    pub regions: Vec<AtlasRegion>, // by texture index, top row first
+    pub pages: Vec<String>,        // paths inside the archive
+}
+
+/// contents of an export archive, with atlas pages still encoded as png
+#[derive(Default)]
+pub struct ExportFile {
+    pub armature: Armature,
+    pub regions: Vec<AtlasRegion>,
+    pub pages: Vec<Vec<u8>>,
+}
+
+pub fn write_export(path: &str, export: &ExportFile) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    let mut pages = vec![];
+    for (i, p) in export.pages.iter().enumerate() {
+        let file = format!("atlas_{}.png", i);
+        zip.start_file(file.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(p).map_err(|e| e.to_string())?;
+        pages.push(file);
+    }
+
+    let description = Export {
+        version: VERSION,
+        armature: export.armature.clone(),
+        regions: export.regions.clone(),
+        pages,
+    };
+    let json = serde_json::to_string(&description).map_err(|e| e.to_string())?;
+    zip.start_file(ARMATURE_FILE, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn read_export(path: &str) -> Result<ExportFile, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let mut json = String::new();
+    zip.by_name(ARMATURE_FILE)
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+    let description: Export = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+    if description.version > VERSION {
+        return Err(format!(
+            "export version {} is newer than supported version {}",
+            description.version, VERSION
+        ));
+    }
+
+    let mut pages = vec![];
+    for p in &description.pages {
+        let mut bytes = vec![];
+        zip.by_name(p)
+            .map_err(|e| e.to_string())?
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+        pages.push(bytes);
+    }
+
+    Ok(ExportFile {
+        armature: description.armature,
+        regions: description.regions,
+        pages,
+    })
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Vec2;
+
+/// 2D affine transform, as the top two rows of a 3x3 matrix:
+///
+/// ```text
+/// | a c x |
+/// | b d y |
+/// | 0 0 1 |
+/// ```
+///
+/// unlike separate position, rotation and scale, this can hold the
+/// shear that comes from rotating under a non-uniformly scaled parent
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Transform2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Transform2D::IDENTITY
+    }
+}
+
+impl Transform2D {
+    #[rustfmt::skip]
+    pub const IDENTITY: Transform2D = Transform2D {
+        a: 1., b: 0.,
+        c: 0., d: 1.,
+        x: 0., y: 0.,
+    };
+
+    /// scale, then rotate, then translate
+    pub fn new(pos: &Vec2, rot: f32, scale: &Vec2) -> Self {
+        let (sin, cos) = rot.sin_cos();
+        Transform2D {
+            a: cos * scale.x,
+            b: sin * scale.x,
+            c: -sin * scale.y,
+            d: cos * scale.y,
+            x: pos.x,
+            y: pos.y,
+        }
+    }
+
+    pub fn from_translation(pos: &Vec2) -> Self {
+        Transform2D {
+            x: pos.x,
+            y: pos.y,
+            ..Transform2D::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: &Vec2) -> Self {
+        Transform2D {
+            a: scale.x,
+            d: scale.y,
+            ..Transform2D::IDENTITY
+        }
+    }
+
+    /// `self * other`, ie apply `other` first and then `self`
+    pub fn compose(&self, other: &Transform2D) -> Self {
+        Transform2D {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            x: self.a * other.x + self.c * other.y + self.x,
+            y: self.b * other.x + self.d * other.y + self.y,
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// `None` if the transform collapses space (eg zero scale)
+    pub fn invert(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        Some(Transform2D {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            x: (self.c * self.y - self.d * self.x) / det,
+            y: (self.b * self.x - self.a * self.y) / det,
+        })
+    }
+
+    pub fn transform_point(&self, p: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.a * p.x + self.c * p.y + self.x,
+            y: self.b * p.x + self.d * p.y + self.y,
+        }
+    }
+
+    /// like `transform_point`, but ignoring translation (for directions and offsets)
+    pub fn transform_vector(&self, v: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.a * v.x + self.c * v.y,
+            y: self.b * v.x + self.d * v.y,
+        }
+    }
+
+    pub fn translation(&self) -> Vec2 {
+        Vec2 {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    /// rotation of the transformed x axis
+    pub fn rotation(&self) -> f32 {
+        self.b.atan2(self.a)
+    }
+
+    /// length of the transformed axes. y is negative if the transform is mirrored
+    pub fn scale(&self) -> Vec2 {
+        let x = (self.a * self.a + self.b * self.b).sqrt();
+        let y = if x == 0. { 0. } else { self.determinant() / x };
+        Vec2 { x, y }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+
+    fn close(a: &Vec2, b: &Vec2) -> bool {
+        (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5
+    }
+
+    #[test]
+    fn rotated_child_under_non_uniform_parent_is_sheared() {
+        let parent = Transform2D::from_scale(&Vec2 { x: 2., y: 1. });
+        let child = Transform2D::new(&Vec2 { x: 1., y: 0. }, FRAC_PI_4, &Vec2 { x: 1., y: 1. });
+        let world = parent.compose(&child);
+
+        // the child's axes are rotated 45 degrees, then stretched along the parent's x
+        let h = FRAC_1_SQRT_2;
+        let x_axis = world.transform_vector(&Vec2 { x: 1., y: 0. });
+        let y_axis = world.transform_vector(&Vec2 { x: 0., y: 1. });
+        assert!(close(&x_axis, &Vec2 { x: 2. * h, y: h }));
+        assert!(close(&y_axis, &Vec2 { x: -2. * h, y: h }));
+
+        // which leaves them no longer perpendicular
+        assert!((x_axis.x * y_axis.x + x_axis.y * y_axis.y).abs() > 0.1);
+
+        let p = world.transform_point(&Vec2 { x: 1., y: 0. });
+        assert!(close(
+            &p,
+            &Vec2 {
+                x: 2. + 2. * h,
+                y: h
+            }
+        ));
+    }
+
+    #[test]
+    fn invert_round_trips() {
+        let t = Transform2D::new(&Vec2 { x: 3., y: -1. }, 0.7, &Vec2 { x: 2., y: 0.5 }).compose(
+            &Transform2D::new(&Vec2 { x: 0.2, y: 4. }, -1.3, &Vec2 { x: -1., y: 3. }),
+        );
+        let inv = t.invert().unwrap();
+        let p = Vec2 { x: 5., y: -2. };
+        assert!(close(&inv.transform_point(&t.transform_point(&p)), &p));
+        assert!(close(&t.transform_point(&inv.transform_point(&p)), &p));
+    }
+
+    #[test]
+    fn zero_scale_has_no_inverse() {
+        let t = Transform2D::new(&Vec2 { x: 1., y: 1. }, 0.3, &Vec2 { x: 0., y: 1. });
+        assert!(t.invert().is_none());
+    }
+
+    #[test]
+    fn mirrored_scale_is_negative() {
+        let t = Transform2D::new(&Vec2::default(), 0.5, &Vec2 { x: 2., y: -3. });
+        let scale = t.scale();
+        assert!((scale.x - 2.).abs() < 1e-5);
+        assert!((scale.y + 3.).abs() < 1e-5);
+
+        let flipped_x = Transform2D::from_scale(&Vec2 { x: -2., y: 3. });
+        assert!(flipped_x.scale().y < 0.);
+        assert!(flipped_x.determinant() < 0.);
+    }
+}
@@ -0,0 +1,52 @@
+use std::f32::consts::PI;
+
+use crate::Vec2;
+
+/// rotate a point via rotation matrix
+pub fn rotate(point: &Vec2, rot: f32) -> Vec2 {
+    Vec2 {
+        x: point.x * rot.cos() - point.y * rot.sin(),
+        y: point.x * rot.sin() + point.y * rot.cos(),
+    }
+}
+
+pub fn lerp(a: &f32, b: &f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+pub fn lerp_vec2(a: &Vec2, b: &Vec2, t: f32) -> Vec2 {
+    Vec2 {
+        x: lerp(&a.x, &b.x, t),
+        y: lerp(&a.y, &b.y, t),
+    }
+}
+
+/// interpolate between angles (in radians) along the shortest path,
+/// so that going from 350° to 10° doesn't spin all the way back
+pub fn lerp_angle(a: &f32, b: &f32, t: f32) -> f32 {
+    let diff = (b - a + PI).rem_euclid(PI * 2.) - PI;
+    a + diff * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_angle_wraps_around() {
+        let (a, b) = (350_f32.to_radians(), 10_f32.to_radians());
+
+        // halfway goes through 0°, not back through 180°
+        let mid = lerp_angle(&a, &b, 0.5).rem_euclid(PI * 2.);
+        assert!(mid < 1e-4 || (PI * 2. - mid) < 1e-4);
+        let quarter = lerp_angle(&a, &b, 0.25).rem_euclid(PI * 2.);
+        assert!((quarter - 355_f32.to_radians()).abs() < 1e-4);
+
+        // and the same the other way
+        let mid = lerp_angle(&b, &a, 0.5).rem_euclid(PI * 2.);
+        assert!(mid < 1e-4 || (PI * 2. - mid) < 1e-4);
+
+        let end = lerp_angle(&a, &b, 1.).rem_euclid(PI * 2.);
+        assert!((end - b).abs() < 1e-4);
+    }
+}
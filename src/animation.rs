@@ -0,0 +1,301 @@
+use skelform_runtime::animation::{pose_armature, pose_draw_order};
+use skelform_runtime::armature::{bone_order, bone_texture};
+
+use crate::mq_backbone::{
+    AnimProperty, Animation, Bone, BoneTrack, Curve, Keyframe, PlayMode, Skelements, Vec2,
+};
+
+pub use skelform_runtime::animation::{find_track, last_frame};
+
+pub fn create_animation(animations: &mut Vec<Animation>) {
+    animations.push(Animation {
+        name: "anim".to_string() + &animations.len().to_string(),
+        fps: 60,
+        ..Default::default()
+    });
+}
+
+/// get this bone's track, creating it if it doesn't exist yet
+pub fn track_mut(anim: &mut Animation, bone_id: i32) -> &mut BoneTrack {
+    let idx = match anim.tracks.iter().position(|t| t.bone_id == bone_id) {
+        Some(idx) => idx,
+        None => {
+            anim.tracks.push(BoneTrack {
+                bone_id,
+                ..Default::default()
+            });
+            anim.tracks.len() - 1
+        }
+    };
+    &mut anim.tracks[idx]
+}
+
+/// key the bone's current pose at this frame, for all properties.
+/// the active attachment is only keyed for bones that have any,
+/// and mesh offsets only for bones showing a mesh or already offset
+pub fn key_bone(anim: &mut Animation, bone: &Bone, frame: i32) {
+    let track = track_mut(anim, bone.id);
+    insert_key(&mut track.pos, frame, bone.pos.clone());
+    insert_key(&mut track.rot, frame, bone.rot);
+    insert_key(&mut track.scale, frame, bone.scale.clone());
+    if !bone.attachments.is_empty() {
+        insert_key(&mut track.attachment, frame, bone.attachment.clone());
+    }
+    let mesh = &bone_texture(bone).mesh;
+    if !mesh.triangles.is_empty() || !bone.deform.is_empty() {
+        // every vertex gets an offset, so blending to the next key moves all of them
+        let mut deform = bone.deform.clone();
+        if deform.len() < mesh.vertices.len() {
+            deform.resize(mesh.vertices.len(), Vec2::default());
+        }
+        insert_key(&mut track.deform, frame, deform);
+    }
+}
+
+/// add a keyframe while keeping them sorted, replacing
+/// any that's already on this frame
+pub fn insert_key<T>(keys: &mut Vec<Keyframe<T>>, frame: i32, value: T) {
+    match keys.binary_search_by_key(&frame, |k| k.frame) {
+        Ok(idx) => keys[idx].value = value,
+        Err(idx) => keys.insert(
+            idx,
+            Keyframe {
+                frame,
+                value,
+                curve: Curve::default(),
+            },
+        ),
+    }
+}
+
+/// frames of all keyframes in one of the track's properties
+pub fn key_frames(track: &BoneTrack, prop: AnimProperty) -> Vec<i32> {
+    match prop {
+        AnimProperty::Pos => track.pos.iter().map(|k| k.frame).collect(),
+        AnimProperty::Rot => track.rot.iter().map(|k| k.frame).collect(),
+        AnimProperty::Scale => track.scale.iter().map(|k| k.frame).collect(),
+        AnimProperty::Attachment => track.attachment.iter().map(|k| k.frame).collect(),
+        AnimProperty::Deform => track.deform.iter().map(|k| k.frame).collect(),
+    }
+}
+
+/// curve of a single keyframe, if it exists
+pub fn find_curve(track: &BoneTrack, prop: AnimProperty, frame: i32) -> Option<&Curve> {
+    #[rustfmt::skip]
+    macro_rules! find_curve {($keys:expr) => {
+        $keys.iter().find(|k| k.frame == frame).map(|k| &k.curve)
+    };}
+
+    match prop {
+        AnimProperty::Pos => find_curve!(track.pos),
+        AnimProperty::Rot => find_curve!(track.rot),
+        AnimProperty::Scale => find_curve!(track.scale),
+        AnimProperty::Attachment => find_curve!(track.attachment),
+        AnimProperty::Deform => find_curve!(track.deform),
+    }
+}
+
+/// same as `find_curve`, for editing it
+pub fn curve_mut(track: &mut BoneTrack, prop: AnimProperty, frame: i32) -> Option<&mut Curve> {
+    #[rustfmt::skip]
+    macro_rules! find_curve {($keys:expr) => {
+        $keys.iter_mut().find(|k| k.frame == frame).map(|k| &mut k.curve)
+    };}
+
+    match prop {
+        AnimProperty::Pos => find_curve!(track.pos),
+        AnimProperty::Rot => find_curve!(track.rot),
+        AnimProperty::Scale => find_curve!(track.scale),
+        AnimProperty::Attachment => find_curve!(track.attachment),
+        AnimProperty::Deform => find_curve!(track.deform),
+    }
+}
+
+pub fn remove_key(track: &mut BoneTrack, prop: AnimProperty, frame: i32) {
+    match prop {
+        AnimProperty::Pos => track.pos.retain(|k| k.frame != frame),
+        AnimProperty::Rot => track.rot.retain(|k| k.frame != frame),
+        AnimProperty::Scale => track.scale.retain(|k| k.frame != frame),
+        AnimProperty::Attachment => track.attachment.retain(|k| k.frame != frame),
+        AnimProperty::Deform => track.deform.retain(|k| k.frame != frame),
+    }
+}
+
+/// move a keyframe to another frame, as long as it's not already occupied
+pub fn move_key(track: &mut BoneTrack, prop: AnimProperty, from: i32, to: i32) -> bool {
+    #[rustfmt::skip]
+    macro_rules! move_in {($keys:expr) => {{
+        if $keys.iter().any(|k| k.frame == to) {
+            return false;
+        }
+        match $keys.iter().position(|k| k.frame == from) {
+            Some(idx) => {
+                let mut key = $keys.remove(idx);
+                key.frame = to;
+                let new_idx = $keys.partition_point(|k| k.frame < to);
+                $keys.insert(new_idx, key);
+                true
+            }
+            None => false,
+        }
+    }};}
+
+    match prop {
+        AnimProperty::Pos => move_in!(track.pos),
+        AnimProperty::Rot => move_in!(track.rot),
+        AnimProperty::Scale => move_in!(track.scale),
+        AnimProperty::Attachment => move_in!(track.attachment),
+        AnimProperty::Deform => move_in!(track.deform),
+    }
+}
+
+/// drop keyframes of a bone that no longer exists
+pub fn remove_bone_tracks(animations: &mut Vec<Animation>, bone_id: i32) {
+    for a in animations {
+        a.tracks.retain(|t| t.bone_id != bone_id);
+        for k in &mut a.draw_order {
+            k.value.retain(|id| *id != bone_id);
+        }
+    }
+}
+
+/// bones as they're shown and edited: posed by the selected animation, or the
+/// setup pose without one. a pose is only kept once it's keyed, as it's
+/// posed again from the setup whenever the frame changes
+pub fn bones(sk: &Skelements) -> &Vec<Bone> {
+    if sk.selected_anim == usize::MAX {
+        &sk.armature.bones
+    } else {
+        &sk.posed_bones
+    }
+}
+
+pub fn bones_mut(sk: &mut Skelements) -> &mut Vec<Bone> {
+    if sk.selected_anim == usize::MAX {
+        &mut sk.armature.bones
+    } else {
+        &mut sk.posed_bones
+    }
+}
+
+/// indices of the shown bones in the order they're drawn (see `bones`)
+pub fn draw_order(sk: &Skelements) -> Vec<usize> {
+    if sk.selected_anim == usize::MAX {
+        bone_order(&sk.armature.bones, &sk.armature.draw_order)
+    } else {
+        bone_order(&sk.posed_bones, &sk.posed_draw_order)
+    }
+}
+
+/// pose copies of the setup bones and draw order with the selected animation,
+/// if the frame changed or bones were added, removed or moved in the hierarchy
+/// since. the latter is done right away by edits that do so, as bones are
+/// looked up by index in both
+pub fn pose(sk: &mut Skelements, frame: f32) {
+    if sk.selected_anim == usize::MAX {
+        return;
+    }
+    let same_hierarchy = sk.posed_bones.len() == sk.armature.bones.len()
+        && sk
+            .posed_bones
+            .iter()
+            .zip(&sk.armature.bones)
+            .all(|(p, b)| p.id == b.id && p.parent_id == b.parent_id);
+    if frame == sk.posed_frame && same_hierarchy {
+        return;
+    }
+
+    let anim = &sk.armature.animations[sk.selected_anim];
+    sk.posed_bones = sk.armature.bones.clone();
+    pose_armature(&mut sk.posed_bones, anim, frame);
+    sk.posed_draw_order = sk.armature.draw_order.clone();
+    pose_draw_order(&mut sk.posed_draw_order, anim, frame);
+    sk.posed_frame = frame;
+}
+
+/// pose the shown bones again at the current frame, such as
+/// after bones were added or removed while animating
+pub fn repose(sk: &mut Skelements) {
+    sk.posed_frame = -1.;
+    pose(sk, sk.anim_frame as f32);
+}
+
+/// advance the playhead and pose the armature accordingly
+pub fn update_playback(sk: &mut Skelements, dt: f32) {
+    if sk.selected_anim == usize::MAX {
+        sk.playing = false;
+        return;
+    }
+    let anim = &sk.armature.animations[sk.selected_anim];
+    let last = last_frame(anim) as f32;
+
+    if sk.playing {
+        let step = dt * anim.fps as f32;
+        if sk.play_forward {
+            sk.play_elapsed += step;
+        } else {
+            sk.play_elapsed -= step;
+        }
+
+        match sk.play_mode {
+            PlayMode::Once => {
+                // either end, as it may still be going backwards
+                // if switched to from ping-pong
+                if sk.play_elapsed >= last {
+                    sk.play_elapsed = last;
+                    sk.playing = false;
+                } else if !sk.play_forward && sk.play_elapsed <= 0. {
+                    sk.play_elapsed = 0.;
+                    sk.playing = false;
+                }
+            }
+            PlayMode::Loop => {
+                if last == 0. {
+                    sk.play_elapsed = 0.;
+                } else {
+                    sk.play_elapsed = sk.play_elapsed.rem_euclid(last);
+                }
+            }
+            PlayMode::PingPong => {
+                // bounce off either end
+                if sk.play_elapsed > last {
+                    sk.play_elapsed = f32::max(last - (sk.play_elapsed - last), 0.);
+                    sk.play_forward = false;
+                } else if sk.play_elapsed < 0. {
+                    sk.play_elapsed = f32::min(-sk.play_elapsed, last);
+                    sk.play_forward = true;
+                }
+            }
+        }
+
+        sk.anim_frame = sk.play_elapsed as i32;
+    }
+
+    let frame = if sk.playing {
+        sk.play_elapsed
+    } else {
+        sk.anim_frame as f32
+    };
+
+    // only pose when the frame changes, so that edits
+    // in between aren't immediately overwritten
+    pose(sk, frame);
+}
+
+pub fn toggle_playing(sk: &mut Skelements) {
+    if sk.selected_anim == usize::MAX {
+        return;
+    }
+    sk.playing = !sk.playing;
+    if !sk.playing {
+        return;
+    }
+
+    // restart if there's nothing left to play
+    let last = last_frame(&sk.armature.animations[sk.selected_anim]);
+    if sk.play_mode == PlayMode::Once && sk.anim_frame >= last {
+        sk.anim_frame = 0;
+    }
+    sk.play_elapsed = sk.anim_frame as f32;
+    sk.play_forward = true;
+}
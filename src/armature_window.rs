@@ -1,17 +1,21 @@
 use egui::*;
+use skelform_runtime::armature::DEFAULT_BONE_LENGTH;
 
-use crate::mq_backbone::{Bone, BoneTexture, Skelements, Vec2};
+use crate::animation;
+use crate::mesh;
+use crate::mq_backbone::{Armature, Bone, BoneTexture, Skelements, Vec2};
+use crate::selection;
 
-pub fn draw_armature(egui_ctx: &Context, skelements: &mut Skelements) {
-    let bones = &mut skelements.armature.bones;
+pub use skelform_runtime::armature::find_bone;
 
+pub fn draw_armature(egui_ctx: &Context, skelements: &mut Skelements) {
     egui::Window::new("Armature")
         .movable(false)
         .show(egui_ctx, |ui| {
             // bone options
             ui.horizontal(|ui| {
                 if ui.button("New Bone").clicked() {
-                    create_bone(bones);
+                    new_bone(skelements);
                 }
                 let drag_name = if skelements.dragging { "Stay" } else { "Drag" };
                 if ui.button(drag_name).clicked() {
@@ -19,9 +23,12 @@ pub fn draw_armature(egui_ctx: &Context, skelements: &mut Skelements) {
                 }
             });
 
+            let bones = &mut skelements.armature.bones;
             if bones.len() == 0 {
                 return;
             }
+            let mut bones_before: Option<Vec<Bone>> = None;
+            let mut clicked: Option<(usize, bool)> = None; // bone, and whether shift was held
 
             // hierarchy
             let frame = Frame::default().inner_margin(10.0);
@@ -51,17 +58,21 @@ pub fn draw_armature(egui_ctx: &Context, skelements: &mut Skelements) {
                                     ui.label(RichText::new(&s.name.to_string()));
                                 })
                                 .response;
-                            check_bone_dragging(bones, ui, d, idx);
+                            if let Some(b) = check_bone_dragging(bones, ui, d, idx) {
+                                bones_before = Some(b);
+                            }
                         } else {
                             // regular, boring buttons
 
                             let mut col = Color32::from_rgb(60, 60, 60);
                             if idx as usize == skelements.selected_bone {
                                 col = Color32::from_rgb(100, 100, 100);
+                            } else if skelements.selected_bones.contains(&(idx as usize)) {
+                                col = Color32::from_rgb(80, 80, 80);
                             }
 
                             if ui.add(Button::new(&s.name.to_string()).fill(col)).clicked() {
-                                skelements.selected_bone = idx as usize;
+                                clicked = Some((idx as usize, ui.input(|i| i.modifiers.shift)));
                             };
                         }
 
@@ -69,6 +80,23 @@ pub fn draw_armature(egui_ctx: &Context, skelements: &mut Skelements) {
                     });
                 }
             });
+
+            // shift adds to or removes from the selection
+            match clicked {
+                Some((idx, true)) => selection::toggle(skelements, idx),
+                Some((idx, false)) => selection::select(skelements, idx),
+                None => {}
+            }
+
+            if let Some(bones) = bones_before {
+                let after = skelements.armature.clone();
+                let before = Armature {
+                    bones,
+                    ..after.clone()
+                };
+                skelements.history.edit_armature(before, after, false);
+                animation::repose(skelements);
+            }
         });
 }
 
@@ -82,11 +110,59 @@ pub fn create_bone(bones: &mut Vec<Bone>) {
             ..BoneTexture::default()
         },
         scale: Vec2 { x: 1., y: 1. },
+        length: DEFAULT_BONE_LENGTH,
         ..Default::default()
     });
 }
 
-fn check_bone_dragging(bones: &mut Vec<Bone>, ui: &mut Ui, drag: Response, idx: i32) {
+/// create a bone as an undoable edit
+pub fn new_bone(skelements: &mut Skelements) {
+    let before = skelements.armature.clone();
+    create_bone(&mut skelements.armature.bones);
+    let after = skelements.armature.clone();
+    skelements.history.edit_armature(before, after, false);
+    animation::repose(skelements);
+}
+
+/// remove the selected bones and their keyframes, as an undoable edit
+pub fn delete_selected_bones(skelements: &mut Skelements) {
+    if skelements.selected_bones.is_empty() {
+        return;
+    }
+    let before = skelements.armature.clone();
+
+    // back to front, so indices of the rest don't shift
+    let mut selected = skelements.selected_bones.clone();
+    selected.sort_unstable_by(|a, b| b.cmp(a));
+    for idx in selected {
+        let removed = skelements.armature.bones.remove(idx);
+        animation::remove_bone_tracks(&mut skelements.armature.animations, removed.id);
+        skelements.armature.draw_order.retain(|id| *id != removed.id);
+        for skin in &mut skelements.armature.skins {
+            skin.attachments.retain(|a| a.bone_id != removed.id);
+        }
+        for b in &mut skelements.armature.bones {
+            mesh::unbind(&mut b.tex.mesh, removed.id);
+            for a in &mut b.attachments {
+                mesh::unbind(&mut a.tex.mesh, removed.id);
+            }
+        }
+    }
+    selection::clear(skelements);
+    skelements.selected_key = None;
+    let after = skelements.armature.clone();
+    skelements.history.edit_armature(before, after, false);
+    animation::repose(skelements);
+}
+
+/// handle drag and drop of bones in the hierarchy.
+/// returns bones as they were before being moved, if they were
+fn check_bone_dragging(
+    bones: &mut Vec<Bone>,
+    ui: &mut Ui,
+    drag: Response,
+    idx: i32,
+) -> Option<Vec<Bone>> {
     if let (Some(pointer), Some(hovered_payload)) = (
         ui.input(|i| i.pointer.interact_pos()),
         drag.dnd_hover_payload::<i32>(),
@@ -120,10 +196,11 @@ fn check_bone_dragging(bones: &mut Vec<Bone>, ui: &mut Ui, drag: Response, idx:
             );
             for c in children {
                 if bones[idx as usize].id == c.id {
-                    return;
+                    return None;
                 }
             }
 
+            let before = bones.clone();
             if move_type == 0 {
                 // move dragged bone above target
                 bones[*dragged_payload as usize].parent_id = bones[idx as usize].clone().id;
@@ -132,9 +209,13 @@ fn check_bone_dragging(bones: &mut Vec<Bone>, ui: &mut Ui, drag: Response, idx:
                 // set dragged bone's parent as target
                 bones[*dragged_payload as usize].parent_id = bones[idx as usize].clone().parent_id;
                 move_bone(bones, *dragged_payload, idx, false);
+            } else {
+                return None;
             }
+            return Some(before);
         }
     }
+    None
 }
 
 pub fn move_bone(bones: &mut Vec<Bone>, old_idx: i32, new_idx: i32, is_setting_parent: bool) {
@@ -184,15 +265,6 @@ pub fn get_all_children(bones: &Vec<Bone>, children_vec: &mut Vec<Bone>, parent:
     }
 }
 
-pub fn find_bone(bones: &Vec<Bone>, id: i32) -> Option<&Bone> {
-    for b in bones {
-        if b.id == id {
-            return Some(&b);
-        }
-    }
-    None
-}
-
 pub fn find_bone_idx(bones: &Vec<Bone>, id: i32) -> i32 {
     let mut i = 0;
     for b in bones {
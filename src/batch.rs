@@ -0,0 +1,137 @@
+use miniquad::*;
+
+use crate::bindings::QUAD_INDICES;
+use crate::mq_backbone::Vertex;
+
+/// what the last frame took to render
+#[derive(Clone, Copy, Default)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    pub vertices: usize,
+    pub textures: usize, // distinct ones bound
+}
+
+/// collects shapes and draws consecutive ones sharing a texture in a single
+/// call. shapes are always drawn in the order they're added, so switching
+/// textures back and forth will still split the batch
+pub struct SpriteBatch {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>, // 32 bit, so big meshes don't split the batch
+    image: Option<TextureId>,
+
+    vertex_buffer: BufferId,
+    index_buffer: BufferId,
+    capacity: usize, // in vertices, with room for 2 indices each
+
+    pub stats: RenderStats,
+    bound: Vec<TextureId>,
+}
+
+impl SpriteBatch {
+    pub fn new(mq_ctx: &mut Box<dyn RenderingBackend>) -> Self {
+        let capacity = 1024;
+        let (vertex_buffer, index_buffer) = new_buffers(mq_ctx, capacity);
+        SpriteBatch {
+            vertices: vec![],
+            indices: vec![],
+            image: None,
+            vertex_buffer,
+            index_buffer,
+            capacity,
+            stats: RenderStats::default(),
+            bound: vec![],
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.stats = RenderStats::default();
+        self.bound.clear();
+    }
+
+    /// add a quad, with vertices going around its corners
+    pub fn quad(
+        &mut self,
+        mq_ctx: &mut Box<dyn RenderingBackend>,
+        image: TextureId,
+        verts: &[Vertex],
+    ) {
+        self.shape(mq_ctx, image, verts, &QUAD_INDICES);
+    }
+
+    pub fn tri(
+        &mut self,
+        mq_ctx: &mut Box<dyn RenderingBackend>,
+        image: TextureId,
+        verts: &[Vertex],
+    ) {
+        self.shape(mq_ctx, image, verts, &[0, 1, 2]);
+    }
+
+    /// add any shape, with `indices` relative to its own vertices
+    pub fn shape(
+        &mut self,
+        mq_ctx: &mut Box<dyn RenderingBackend>,
+        image: TextureId,
+        verts: &[Vertex],
+        indices: &[u32],
+    ) {
+        if self.image != Some(image) {
+            self.flush(mq_ctx);
+            self.image = Some(image);
+        }
+
+        let base = self.vertices.len() as u32;
+        self.indices.extend(indices.iter().map(|i| base + i));
+        self.vertices.extend_from_slice(verts);
+    }
+
+    /// draw everything collected so far
+    pub fn flush(&mut self, mq_ctx: &mut Box<dyn RenderingBackend>) {
+        let image = match self.image {
+            Some(i) if !self.indices.is_empty() => i,
+            _ => return,
+        };
+
+        // buffers can't be resized, so replace them with bigger ones
+        if self.vertices.len() > self.capacity || self.indices.len() > self.capacity * 2 {
+            mq_ctx.delete_buffer(self.vertex_buffer);
+            mq_ctx.delete_buffer(self.index_buffer);
+            self.capacity =
+                usize::max(self.vertices.len(), self.indices.len() / 2).next_power_of_two();
+            (self.vertex_buffer, self.index_buffer) = new_buffers(mq_ctx, self.capacity);
+        }
+
+        mq_ctx.buffer_update(self.vertex_buffer, BufferSource::slice(&self.vertices));
+        mq_ctx.buffer_update(self.index_buffer, BufferSource::slice(&self.indices));
+        mq_ctx.apply_bindings(&Bindings {
+            vertex_buffers: vec![self.vertex_buffer],
+            index_buffer: self.index_buffer,
+            images: vec![image],
+        });
+        mq_ctx.draw(0, self.indices.len() as i32, 1);
+
+        self.stats.draw_calls += 1;
+        self.stats.vertices += self.vertices.len();
+        if !self.bound.contains(&image) {
+            self.bound.push(image);
+            self.stats.textures += 1;
+        }
+
+        self.vertices.clear();
+        self.indices.clear();
+    }
+}
+
+fn new_buffers(mq_ctx: &mut Box<dyn RenderingBackend>, capacity: usize) -> (BufferId, BufferId) {
+    let vertex_buffer = mq_ctx.new_buffer(
+        BufferType::VertexBuffer,
+        BufferUsage::Stream,
+        BufferSource::empty::<Vertex>(capacity),
+    );
+    let index_buffer = mq_ctx.new_buffer(
+        BufferType::IndexBuffer,
+        BufferUsage::Stream,
+        BufferSource::empty::<u32>(capacity * 2),
+    );
+    (vertex_buffer, index_buffer)
+}
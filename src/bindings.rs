@@ -1,93 +1,130 @@
+use std::collections::HashMap;
+
 use miniquad::*;
+use skelform_runtime::armature::vertex_transform;
+use skelform_runtime::atlas::{mesh_point, mesh_uv, sprite_rect};
 
-use crate::mq_backbone::Texture;
+use crate::mq_backbone::Transform2D;
 use crate::mq_backbone::Vec2;
 use crate::mq_backbone::Vertex;
-use crate::utils::rotate;
-
-pub fn rect_tex_verts(pos: &Vec2, scale: &Vec2, size: &Vec2, rot: f32) -> Vec<Vertex> {
-    let hard_scale = 0.001;
-    let mut vertices: Vec<Vertex> = vec![
-        Vertex {
-            pos: Vec2 {
-                x: -hard_scale * scale.x * size.x as f32,
-                y: -hard_scale * scale.y * size.y as f32,
-            },
-            uv: Vec2 { x: 0., y: 0. },
-        },
-        Vertex {
-            pos: Vec2 {
-                x: hard_scale * scale.x * size.x as f32,
-                y: -hard_scale * scale.y * size.y as f32,
-            },
-            uv: Vec2 { x: 1., y: 0. },
-        },
-        Vertex {
-            pos: Vec2 {
-                x: hard_scale * scale.x * size.x as f32,
-                y: hard_scale * scale.y * size.y as f32,
-            },
-            uv: Vec2 { x: 1., y: 1. },
-        },
-        Vertex {
-            pos: Vec2 {
-                x: -hard_scale * scale.x * size.x as f32,
-                y: hard_scale * scale.y * size.y as f32,
-            },
-            uv: Vec2 { x: 0., y: 1. },
-        },
+use crate::mq_backbone::{Atlas, AtlasRegion, BoneTexture};
+
+/// corners of a texture's region in the atlas, placed where they'd be in the
+/// full image, with its pivot on the origin of `transform`. no region gives
+/// an empty quad
+pub fn rect_tex_verts(
+    transform: &Transform2D,
+    region: Option<&AtlasRegion>,
+    tex: &BoneTexture,
+) -> Vec<Vertex> {
+    let region = match region {
+        Some(r) => r.clone(),
+        None => AtlasRegion::default(),
+    };
+    let [left, bottom, right, top] = sprite_rect(&region, tex);
+
+    let (min, max) = (&region.uv.min, &region.uv.max);
+    #[rustfmt::skip]
+    let corners = [
+        (left,  bottom, min.x, min.y),
+        (right, bottom, max.x, min.y),
+        (right, top,    max.x, max.y),
+        (left,  top,    min.x, max.y),
     ];
 
-    for v in &mut vertices {
-        v.pos = rotate(&v.pos, rot);
+    corners
+        .iter()
+        .map(|(x, y, u, v)| Vertex {
+            pos: transform.transform_point(&Vec2 { x: *x, y: *y }),
+            uv: Vec2 { x: *u, y: *v },
+        })
+        .collect()
+}
 
-        // move vertex with bone
-        v.pos.x += pos.x;
-        v.pos.y += pos.y;
-    }
+/// triangles of a quad from `rect_tex_verts`
+pub const QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+/// vertices of a texture's mesh placed like `rect_tex_verts` does, with each
+/// moved by its offset in `deform` (in the space `transform` maps from).
+/// weighted vertices are placed by blending `skin` instead, which maps from
+/// the same space for each of the mesh's bones (see `skin_transforms`)
+pub fn mesh_tex_verts(
+    transform: &Transform2D,
+    region: &AtlasRegion,
+    tex: &BoneTexture,
+    deform: &[Vec2],
+    skin: &[Transform2D],
+) -> Vec<Vertex> {
+    let mesh = &tex.mesh;
+    (0..mesh.vertices.len())
+        .map(|i| {
+            let mut p = mesh_point(region, tex, &mesh.vertices[i]);
+            if let Some(d) = deform.get(i) {
+                p.x += d.x;
+                p.y += d.y;
+            }
+            Vertex {
+                pos: vertex_transform(mesh, i, transform, skin).transform_point(&p),
+                uv: mesh_uv(region, &mesh.vertices[i]),
+            }
+        })
+        .collect()
+}
 
-    vertices
+/// vertices and triangles to draw a texture with: its mesh if it has
+/// one, or a quad otherwise
+pub fn sprite_verts(
+    transform: &Transform2D,
+    region: Option<&AtlasRegion>,
+    tex: &BoneTexture,
+    deform: &[Vec2],
+    skin: &[Transform2D],
+) -> (Vec<Vertex>, Vec<u32>) {
+    match region {
+        Some(r) if !tex.mesh.triangles.is_empty() => (
+            mesh_tex_verts(transform, r, tex, deform, skin),
+            tex.mesh.triangles.clone(),
+        ),
+        _ => (
+            rect_tex_verts(transform, region, tex),
+            QUAD_INDICES.to_vec(),
+        ),
+    }
 }
 
-/// Creates a rectangular texture.
-pub fn rect_tex(
-    mq_ctx: &mut Box<dyn RenderingBackend>,
-    pos: &Vec2,
-    scale: &Vec2,
-    texture: &Texture,
-    rot: f32,
-) -> (Bindings, Vec<Vertex>) {
-    let vertices = rect_tex_verts(
-        &pos,
-        &scale,
-        &texture.size,
-        rot,
-    );
+/// textures that outlive a single frame, so nothing is
+/// uploaded again unless it changed
+#[derive(Default)]
+pub struct GpuCache {
+    // uploaded atlas pages
+    pages: Vec<TextureId>,
 
-    let vertex_buffer = mq_ctx.new_buffer(
-        BufferType::VertexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&vertices),
-    );
+    // 1x1 textures for flat colors
+    colors: HashMap<[u8; 4], TextureId>,
+}
 
-    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
-    let index_buffer = mq_ctx.new_buffer(
-        BufferType::IndexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&indices),
-    );
+impl GpuCache {
+    /// replace uploaded pages with those of a newly packed atlas
+    pub fn upload_atlas(&mut self, mq_ctx: &mut Box<dyn RenderingBackend>, atlas: &Atlas) {
+        for p in self.pages.drain(..) {
+            mq_ctx.delete_texture(p);
+        }
+        for p in &atlas.pages {
+            let id = mq_ctx.new_texture_from_rgba8(p.width as u16, p.height as u16, &p.rgba);
+            self.pages.push(id);
+        }
+    }
 
-    let tex =
-        mq_ctx.new_texture_from_rgba8(texture.size.x as u16, texture.size.y as u16, &texture.bytes);
+    pub fn page(&self, idx: usize) -> TextureId {
+        self.pages[idx]
+    }
 
-    (
-        Bindings {
-            vertex_buffers: vec![vertex_buffer],
-            index_buffer,
-            images: vec![tex],
-        },
-        vertices,
-    )
+    pub fn color(&mut self, mq_ctx: &mut Box<dyn RenderingBackend>, color: [u8; 4]) -> TextureId {
+        *self
+            .colors
+            .entry(color)
+            .or_insert_with(|| mq_ctx.new_texture_from_rgba8(1, 1, &color))
+    }
 }
 
 /// For quick testing purposes
@@ -146,98 +183,3 @@ pub fn placeholder_binding(mq_ctx: &mut Box<dyn RenderingBackend>) -> Bindings {
         images: vec![texture],
     }
 }
-
-pub fn rect_bind(mq_ctx: &mut Box<dyn RenderingBackend>, vertices: &Vec<Vertex>, size: &Vec2, color: [u8; 4]) -> Bindings {
-    let vertex_buffer = mq_ctx.new_buffer(
-        BufferType::VertexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&vertices),
-    );
-
-    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
-    let index_buffer = mq_ctx.new_buffer(
-        BufferType::IndexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&indices),
-    );
-
-    #[rustfmt::skip]
-    let texture = mq_ctx.new_texture_from_rgba8(1, 1, &color);
-
-    Bindings {
-        vertex_buffers: vec![vertex_buffer],
-        index_buffer,
-        images: vec![texture],
-    }
-}
-
-/// Creates a rectangular texture.
-pub fn tri_bind(
-    mq_ctx: &mut Box<dyn RenderingBackend>,
-    pos: Vec2,
-    offset: Vec2,
-    scale: Vec2,
-    rot: f32,
-    color: [u8; 4],
-) -> (Bindings, Vec<Vertex>) {
-    let mut vertices: Vec<Vertex> = vec![
-        Vertex {
-            pos: Vec2 {
-                x: -scale.x + offset.x,
-                y: offset.y,
-            },
-            uv: Vec2::default(),
-        },
-        Vertex {
-            pos: Vec2 {
-                x: offset.x,
-                y: scale.y + offset.y,
-            },
-            uv: Vec2::default(),
-        },
-        Vertex {
-            pos: Vec2 {
-                x: scale.x + offset.x,
-                y: offset.y,
-            },
-            uv: Vec2::default(),
-        },
-    ];
-
-    for v in &mut vertices {
-        // rotate vertex
-        v.pos = Vec2 {
-            x: v.pos.x * rot.cos() - v.pos.y * rot.sin(),
-            y: v.pos.x * rot.sin() + v.pos.y * rot.cos(),
-        };
-
-        // move vertex with bone
-        v.pos.x += pos.x;
-        v.pos.y += pos.y;
-    }
-
-    let vertex_buffer = mq_ctx.new_buffer(
-        BufferType::VertexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&vertices),
-    );
-
-    let indices: [u16; 3] = [0, 1, 2];
-    let index_buffer = mq_ctx.new_buffer(
-        BufferType::IndexBuffer,
-        BufferUsage::Immutable,
-        BufferSource::slice(&indices),
-    );
-
-    #[rustfmt::skip]
-    let pixels: [u8; 4] = [color[0], color[1], color[2], 0xFF];
-    let tex = mq_ctx.new_texture_from_rgba8(1, 1, &pixels);
-    (
-        Bindings {
-            vertex_buffers: vec![vertex_buffer],
-            index_buffer,
-            images: vec![tex],
-        },
-        vertices,
-    )
-}
@@ -3,9 +3,17 @@ use std::io::Write;
 use std::rc::Rc;
 use std::{fs::File, thread};
 
-use egui::{Align2, Context, Layout, Ui, Vec2};
+use egui::{
+    Align2, Button, ComboBox, Context, DragValue, Grid, Layout, ScrollArea, Slider, Ui, Vec2,
+};
 
-use crate::mq_backbone::{Bone, Skelements};
+use skelform_runtime::armature::{active_attachment, bone_texture, bone_texture_mut, find_bone};
+
+use crate::animation;
+use crate::armature_window;
+use crate::mesh;
+use crate::mq_backbone::{Attachment, Bone, BoneTexture, Mesh, Skelements};
+use crate::skins_window;
 
 pub fn draw_bone(egui_ctx: &Context, skelements: &mut Skelements) {
     egui::Window::new("Bone")
@@ -23,87 +31,349 @@ pub fn draw_bone(egui_ctx: &Context, skelements: &mut Skelements) {
                 let l = ui.label("Name:");
                 ui.text_edit_singleline(&mut bone.name).labelled_by(l.id);
             });
+
+            // only the pose is animated, so the rest is edited in the setup pose
+            let animating = skelements.selected_anim != usize::MAX;
             ui.horizontal(|ui| {
                 ui.label("Texture:");
                 let bone_idx = skelements.selected_bone;
-                if ui.button("Get Image").clicked() {
-                    open_file_dialog(bone_idx);
+                if ui
+                    .add_enabled(!animating, Button::new("Get Image"))
+                    .clicked()
+                {
+                    open_file_dialog(bone_idx, None);
                 };
             });
             if skelements.selected_bone == usize::MAX {
                 return;
             }
+
+            let bone_idx = skelements.selected_bone;
+            let before = animation::bones(skelements)[bone_idx].clone();
+            let bone = &mut animation::bones_mut(skelements)[bone_idx];
             ui.horizontal(|ui| {
                 ui.label("Position:");
                 ui.label("x:");
-                float_input(
-                    ui,
-                    &mut skelements.armature.bones[skelements.selected_bone].pos.x,
-                );
+                float_input(ui, &mut bone.pos.x);
                 ui.label("y:");
-                float_input(
-                    ui,
-                    &mut skelements.armature.bones[skelements.selected_bone].pos.y,
-                );
+                float_input(ui, &mut bone.pos.y);
             });
             ui.horizontal(|ui| {
                 ui.label("Scale:");
                 ui.label("x:");
-                float_input(
-                    ui,
-                    &mut skelements.armature.bones[skelements.selected_bone].scale.x,
-                );
+                float_input(ui, &mut bone.scale.x);
                 ui.label("y:");
-                float_input(
-                    ui,
-                    &mut skelements.armature.bones[skelements.selected_bone].scale.y,
-                );
+                float_input(ui, &mut bone.scale.y);
             });
             ui.horizontal(|ui| {
                 ui.label("Rotation:");
-                let deg = skelements.armature.bones[skelements.selected_bone].rot / PI * 180.;
-                let mut str = deg.round().to_string();
-                if !str.contains(".") {
-                    str.push('.');
-                }
-                ui.add_sized([30., 20.], egui::TextEdit::singleline(&mut str));
-                if let Ok(f) = str.parse::<f32>() {
-                    skelements.armature.bones[skelements.selected_bone].rot = f * PI / 180.;
-                } else {
-                    skelements.armature.bones[skelements.selected_bone].rot = 0.;
-                }
+                angle_input(ui, &mut bone.rot);
             });
+
+            ui.add_enabled_ui(!animating, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Length:");
+                    float_input(ui, &mut bone.length);
+                });
+            });
+            ui.add_enabled_ui(!animating, |ui| {
+                draw_texture(ui, skelements, bone_idx);
+                draw_weights(ui, skelements, bone_idx);
+            });
+
+            let bone = &mut animation::bones_mut(skelements)[bone_idx];
+            draw_attachments(ui, bone, bone_idx, animating);
+
+            // typing into the same field is a single undo. inputs only write
+            // when edited, so nothing is recorded for a bone that's just shown.
+            // poses are undone by keying them instead
+            if !animating {
+                let after = skelements.armature.bones[bone_idx].clone();
+                skelements.history.edit_bone(bone_idx, before, after, true);
+            }
+
             if ui.button("Delete Bone").clicked() {
-                skelements.armature.bones.remove(skelements.selected_bone);
-                skelements.selected_bone = usize::MAX;
+                armature_window::delete_selected_bones(skelements);
+            };
+        });
+}
+
+/// pivot, offset and mesh of whichever image is being shown, which the skin may pick
+fn draw_texture(ui: &mut Ui, skelements: &mut Skelements, bone_idx: usize) {
+    let shown = skins_window::skinned(skelements, &animation::bones(skelements)[bone_idx]);
+    let bone = &mut skelements.armature.bones[bone_idx];
+    let own_attachment = std::mem::replace(&mut bone.attachment, shown.attachment);
+    let tex = bone_texture_mut(bone);
+    ui.horizontal(|ui| {
+        ui.label("Pivot:");
+        ui.label("x:");
+        float_input(ui, &mut tex.pivot.x);
+        ui.label("y:");
+        float_input(ui, &mut tex.pivot.y);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Offset:");
+        ui.label("x:");
+        float_input(ui, &mut tex.offset.x);
+        ui.label("y:");
+        float_input(ui, &mut tex.offset.y);
+    });
+
+    // offsets are per vertex, so they don't carry over to a new mesh
+    let texture = skelements.textures.get(tex.idx);
+    let mut mesh_changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Mesh:");
+        if ui
+            .add_enabled(texture.is_some(), Button::new("Trace"))
+            .clicked()
+        {
+            if let Some(t) = texture {
+                tex.mesh = mesh::trace(t);
+                mesh_changed = true;
+            }
+        }
+        let has_mesh = !tex.mesh.triangles.is_empty();
+        if ui.add_enabled(has_mesh, Button::new("Remove")).clicked() {
+            tex.mesh = Mesh::default();
+            mesh_changed = true;
+        }
+        ui.label(format!("{} vertices", tex.mesh.vertices.len()));
+    });
+    bone.attachment = own_attachment;
+    if mesh_changed {
+        bone.deform.clear();
+    }
+}
+
+/// weights of the shown mesh's vertices, for bending it smoothly where
+/// bones meet. edits go to a copy, which is only put back if it changed
+fn draw_weights(ui: &mut Ui, sk: &mut Skelements, bone_idx: usize) {
+    let shown = skins_window::skinned(sk, &animation::bones(sk)[bone_idx]);
+    let mut mesh = bone_texture(&shown).mesh.clone();
+    if mesh.triangles.is_empty() {
+        return;
+    }
+    let owner_id = shown.id;
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Weights:");
+        let auto = ui.button("Auto").on_hover_text(
+            "Weight vertices to the closest of the selected bones, \
+            or of every bone if only this one is selected",
+        );
+        if auto.clicked() {
+            let candidates: Vec<usize> = if sk.selected_bones.len() > 1 {
+                sk.selected_bones.clone()
+            } else {
+                (0..sk.armature.bones.len()).collect()
             };
+            if let Some(m) = mesh::auto_weights(sk, bone_idx, &candidates) {
+                mesh = m;
+            }
+        }
+        if ui
+            .add_enabled(!mesh.bones.is_empty(), Button::new("Clear"))
+            .clicked()
+        {
+            mesh.bones.clear();
+            mesh.weights.clear();
+        }
+    });
+
+    // which bone's weights the brush paints
+    ui.horizontal(|ui| {
+        ui.label("Paint:");
+        let selected_name = match find_bone(&sk.armature.bones, sk.weight_bone) {
+            Some(b) => b.name.clone(),
+            None => "(none)".to_string(),
+        };
+        let mut weight_bone = sk.weight_bone;
+        ComboBox::from_id_source("weight_bone")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for b in &sk.armature.bones {
+                    ui.selectable_value(&mut weight_bone, b.id, &b.name);
+                }
+            });
+        sk.weight_bone = weight_bone;
+    });
+    ui.add(Slider::new(&mut sk.brush_strength, 0.0..=1.0).text("Strength"));
+
+    if mesh.bones.is_empty() {
+        ui.label("Vertices only follow this bone.");
+    } else {
+        // a row for each vertex, with a column for each bone it can follow
+        ScrollArea::both().max_height(150.).show(ui, |ui| {
+            Grid::new("weights").striped(true).show(ui, |ui| {
+                ui.label("#");
+                for mb in &mesh.bones {
+                    match find_bone(&sk.armature.bones, mb.bone_id) {
+                        Some(b) => ui.label(&b.name),
+                        None => ui.label("?"),
+                    };
+                }
+                ui.end_row();
+
+                for v in 0..mesh.vertices.len() {
+                    ui.label(v.to_string());
+                    for i in 0..mesh.bones.len() {
+                        let id = mesh.bones[i].bone_id;
+                        let mut weight = mesh::weight(&mesh, v, id, owner_id);
+                        let input = DragValue::new(&mut weight)
+                            .speed(0.01)
+                            .range(0.0..=1.0)
+                            .max_decimals(2);
+                        if ui.add(input).changed() {
+                            mesh::set_weight(&mut mesh, v, id, weight, owner_id);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    if mesh == bone_texture(&shown).mesh {
+        return;
+    }
+    let bone = &mut sk.armature.bones[bone_idx];
+    let own_attachment = std::mem::replace(&mut bone.attachment, shown.attachment.clone());
+    bone_texture_mut(bone).mesh = mesh;
+    bone.attachment = own_attachment;
+}
+
+/// list of a bone's attachments, for picking which one is shown and editing them.
+/// only the one being shown is animated, so the rest can't be edited while `animating`
+fn draw_attachments(ui: &mut Ui, bone: &mut Bone, bone_idx: usize, animating: bool) {
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Attachments:");
+        if ui.add_enabled(!animating, Button::new("Add")).clicked() {
+            let mut n = bone.attachments.len();
+            while bone
+                .attachments
+                .iter()
+                .any(|a| a.name == format!("attachment{}", n))
+            {
+                n += 1;
+            }
+            bone.attachments.push(Attachment {
+                name: format!("attachment{}", n),
+                tex: BoneTexture {
+                    idx: usize::MAX,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+    });
+
+    // showing none of them falls back to the bone's own texture
+    let none = active_attachment(bone).is_none();
+    if ui.selectable_label(none, "(bone texture)").clicked() {
+        bone.attachment.clear();
+    }
+
+    let mut removed = None;
+    for (i, a) in bone.attachments.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let active = !bone.attachment.is_empty() && bone.attachment == a.name;
+            if ui.radio(active, "").clicked() {
+                bone.attachment = a.name.clone();
+            }
+            ui.add_enabled_ui(!animating, |ui| {
+                ui.add_sized([60., 20.], egui::TextEdit::singleline(&mut a.name));
+                if active {
+                    // keep showing it while renaming
+                    bone.attachment = a.name.clone();
+                }
+                if ui.button("Image").clicked() {
+                    open_file_dialog(bone_idx, Some(i));
+                }
+                if ui.button("X").clicked() {
+                    removed = Some(i);
+                }
+            });
+        });
+    }
+    if let Some(i) = removed {
+        bone.attachments.remove(i);
+    }
+
+    // rotation and scale of the one being shown
+    let name = bone.attachment.clone();
+    let shown = bone.attachments.iter_mut().find(|a| a.name == name);
+    if let Some(a) = shown.filter(|_| !name.is_empty()) {
+        ui.add_enabled_ui(!animating, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Rotation:");
+                angle_input(ui, &mut a.rot);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scale:");
+                ui.label("x:");
+                float_input(ui, &mut a.scale.x);
+                ui.label("y:");
+                float_input(ui, &mut a.scale.y);
+            });
         });
+    }
 }
 
-fn open_file_dialog(bone_idx: usize) {
+/// pick an image for a bone, or one of its attachments
+fn open_file_dialog(bone_idx: usize, attachment_idx: Option<usize>) {
     thread::spawn(move || {
         let task = rfd::FileDialog::new().pick_file();
-        let mut img_path = File::create(".skelform_img_path").unwrap();
-        img_path
-            .write_all(task.unwrap().as_path().to_str().unwrap().as_bytes())
-            .unwrap();
+
+        // written before the image path, which is what gets checked for
+        if let Some(idx) = attachment_idx {
+            let mut attachment_file = File::create(".skelform_attachment_idx").unwrap();
+            attachment_file
+                .write_all(idx.to_string().as_bytes())
+                .unwrap();
+        }
         let mut bone_idx_file = File::create(".skelform_bone_idx").unwrap();
         bone_idx_file
             .write_all(bone_idx.to_string().as_bytes())
             .unwrap();
+        let mut img_path = File::create(".skelform_img_path").unwrap();
+        img_path
+            .write_all(task.unwrap().as_path().to_str().unwrap().as_bytes())
+            .unwrap();
     });
 }
 
-// helper for editable float inputs
+// helper for editable angles, shown in degrees. the angle is only
+// written when the field is edited, so showing it doesn't round it
+fn angle_input(ui: &mut Ui, angle: &mut f32) {
+    let deg = *angle / PI * 180.;
+    let mut str = deg.round().to_string();
+    if !str.contains(".") {
+        str.push('.');
+    }
+    let r = ui.add_sized([30., 20.], egui::TextEdit::singleline(&mut str));
+    if !r.changed() {
+        return;
+    }
+    if let Ok(f) = str.parse::<f32>() {
+        *angle = f * PI / 180.;
+    }
+}
+
+// helper for editable float inputs. text that isn't a number
+// (such as while it's being typed) leaves the value as it was
 fn float_input(ui: &mut Ui, float: &mut f32) {
     let mut str = float.to_string();
     if !str.contains(".") {
         str.push('.');
     }
-    ui.add_sized([30., 20.], egui::TextEdit::singleline(&mut str));
+    let r = ui.add_sized([30., 20.], egui::TextEdit::singleline(&mut str));
+    if !r.changed() {
+        return;
+    }
     if let Ok(f) = str.parse::<f32>() {
         *float = f;
-    } else {
-        *float = 0.;
     }
 }
@@ -0,0 +1,223 @@
+use skelform_runtime::armature::{bone_texture, image_transform};
+use skelform_runtime::atlas::bone_region;
+
+use crate::animation;
+use crate::armature_window;
+use crate::bindings::sprite_verts;
+use crate::mesh;
+use crate::mq_backbone::{Camera, Skelements, Transform2D, Vec2};
+use crate::skins_window;
+
+pub const MIN_ZOOM: f32 = 0.05;
+pub const MAX_ZOOM: f32 = 50.;
+pub const ZOOM_PRESETS: [f32; 5] = [0.25, 0.5, 1., 2., 4.];
+pub const ZOOM_STEP: f32 = 1.1; // per notch of the mouse wheel
+
+// share of the window that framed bones take up
+const FRAME_FILL: f32 = 0.8;
+
+/*
+    spaces, from outside in:
+    - window: miniquad events, in physical pixels, y down
+    - screen: egui points (pixels divided by dpi and egui's zoom), y down.
+      `Skelements::mouse` and `window_size` are in this
+    - clip: -1 to 1 across the window, y up
+    - world: what bones are placed in, y up. the camera maps it to clip
+    - bone-local: relative to a bone's parent, which is what `Bone::pos` is in
+
+    everything that converts between these should go through here, so a
+    point dragged with the mouse stays under it no matter the zoom or window
+*/
+
+/// transform from world to clip space, based on camera and window
+pub fn view_transform(camera: &Camera, window_size: &Vec2) -> Transform2D {
+    let mut scale = Vec2 {
+        x: camera.zoom,
+        y: camera.zoom,
+    };
+
+    // squash the longer side, so that things aren't stretched with the window
+    if window_size.x != 0. && window_size.y != 0. {
+        let ratio = f32::max(window_size.x, window_size.y) / f32::min(window_size.x, window_size.y);
+        if window_size.x > window_size.y {
+            scale.x /= ratio;
+        } else {
+            scale.y /= ratio;
+        }
+    }
+
+    let cam_offset = Vec2 {
+        x: -camera.pos.x,
+        y: -camera.pos.y,
+    };
+    Transform2D::from_scale(&scale).compose(&Transform2D::from_translation(&cam_offset))
+}
+
+/// convert a position from a miniquad event to screen space
+pub fn window_to_screen(x: f32, y: f32, pixels_per_point: f32) -> Vec2 {
+    Vec2 {
+        x: x / pixels_per_point,
+        y: y / pixels_per_point,
+    }
+}
+
+/// convert a point on the window (in points, y down) to clip space
+pub fn screen_to_clip(point: &Vec2, window_size: &Vec2) -> Vec2 {
+    let half = Vec2 {
+        x: window_size.x / 2.,
+        y: window_size.y / 2.,
+    };
+    if half.x == 0. || half.y == 0. {
+        return Vec2::default();
+    }
+    Vec2 {
+        x: (point.x - half.x) / half.x,
+        y: (half.y - point.y) / half.y,
+    }
+}
+
+pub fn clip_to_screen(point: &Vec2, window_size: &Vec2) -> Vec2 {
+    Vec2 {
+        x: (point.x + 1.) * window_size.x / 2.,
+        y: (1. - point.y) * window_size.y / 2.,
+    }
+}
+
+pub fn world_to_screen(point: &Vec2, camera: &Camera, window_size: &Vec2) -> Vec2 {
+    let clip = view_transform(camera, window_size).transform_point(point);
+    clip_to_screen(&clip, window_size)
+}
+
+pub fn screen_to_world(point: &Vec2, camera: &Camera, window_size: &Vec2) -> Vec2 {
+    let clip = screen_to_clip(point, window_size);
+    match view_transform(camera, window_size).invert() {
+        Some(inv) => inv.transform_point(&clip),
+        None => Vec2::default(),
+    }
+}
+
+/// transform from world space to the space a bone's position is in,
+/// which is its parent's (or the world, for bones without one)
+pub fn world_to_parent(sk: &Skelements, idx: usize) -> Transform2D {
+    let parent_id = sk.armature.bones[idx].parent_id;
+    let parent_idx = armature_window::find_bone_idx(&sk.armature.bones, parent_id);
+    if parent_idx == -1 || parent_idx as usize >= sk.world_transforms.len() {
+        return Transform2D::IDENTITY;
+    }
+    sk.world_transforms[parent_idx as usize]
+        .invert()
+        .unwrap_or(Transform2D::IDENTITY)
+}
+
+/// transform from world space to a bone's own space, which is what its
+/// children's positions and its texture's offset are in
+pub fn world_to_bone(sk: &Skelements, idx: usize) -> Transform2D {
+    match sk.world_transforms.get(idx) {
+        Some(w) => w.invert().unwrap_or(Transform2D::IDENTITY),
+        None => Transform2D::IDENTITY,
+    }
+}
+
+/// move the camera so that whatever was under `from` is now under `to`
+/// (both in screen space), like grabbing the canvas itself
+pub fn pan(camera: &mut Camera, from: &Vec2, to: &Vec2, window_size: &Vec2) {
+    let a = screen_to_world(from, camera, window_size);
+    let b = screen_to_world(to, camera, window_size);
+    camera.pos.x -= b.x - a.x;
+    camera.pos.y -= b.y - a.y;
+}
+
+/// keep the camera from zooming so far that the view transform breaks down
+pub fn clamp_zoom(zoom: f32) -> f32 {
+    zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// set the zoom while keeping whatever is under `point` (in screen space)
+/// in place, so zooming heads towards the mouse
+pub fn zoom_at(camera: &mut Camera, point: &Vec2, zoom: f32, window_size: &Vec2) {
+    let before = screen_to_world(point, camera, window_size);
+    camera.zoom = clamp_zoom(zoom);
+    let after = screen_to_world(point, camera, window_size);
+    camera.pos.x += before.x - after.x;
+    camera.pos.y += before.y - after.y;
+}
+
+/// smallest world space rect holding everything the given bones show,
+/// along with their origins for bones without a texture
+pub fn bone_bounds(sk: &Skelements, bones: &[usize]) -> Option<(Vec2, Vec2)> {
+    let mut points = vec![];
+    for &idx in bones {
+        let world = match sk.world_transforms.get(idx) {
+            Some(w) => w,
+            None => continue,
+        };
+        points.push(world.translation());
+        if let Some(atlas) = &sk.atlas {
+            let bone = &skins_window::skinned(sk, &animation::bones(sk)[idx]);
+            let image = world.compose(&image_transform(bone));
+            let region = bone_region(atlas, bone);
+            let skin = mesh::skin(sk, idx, bone, &Transform2D::IDENTITY);
+            let (verts, _) = sprite_verts(&image, region, bone_texture(bone), &bone.deform, &skin);
+            points.extend(verts.into_iter().map(|v| v.pos));
+        }
+    }
+
+    let first = points.first()?.clone();
+    let (mut min, mut max) = (first.clone(), first);
+    for p in &points {
+        min.x = f32::min(min.x, p.x);
+        min.y = f32::min(min.y, p.y);
+        max.x = f32::max(max.x, p.x);
+        max.y = f32::max(max.y, p.y);
+    }
+    Some((min, max))
+}
+
+/// center the camera on a world space rect, zooming so it fills most of the
+/// window. a rect with no size (like a single bone) only gets centered on
+pub fn frame(camera: &mut Camera, min: &Vec2, max: &Vec2, window_size: &Vec2) {
+    camera.pos = Vec2 {
+        x: (min.x + max.x) / 2.,
+        y: (min.y + max.y) / 2.,
+    };
+
+    // how much of clip space a world unit takes up at a zoom of 1
+    let unit = view_transform(
+        &Camera {
+            pos: Vec2::default(),
+            zoom: 1.,
+        },
+        window_size,
+    )
+    .scale();
+
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    let mut zoom = f32::MAX;
+    if width > 0. {
+        zoom = f32::min(zoom, 2. * FRAME_FILL / (width * unit.x));
+    }
+    if height > 0. {
+        zoom = f32::min(zoom, 2. * FRAME_FILL / (height * unit.y));
+    }
+    if zoom != f32::MAX {
+        camera.zoom = clamp_zoom(zoom);
+    }
+}
+
+/// frame the selected bones, or everything if nothing is selected
+pub fn frame_selected(sk: &mut Skelements) {
+    if sk.selected_bones.is_empty() {
+        frame_all(sk);
+        return;
+    }
+    if let Some((min, max)) = bone_bounds(sk, &sk.selected_bones) {
+        frame(&mut sk.camera, &min, &max, &sk.window_size);
+    }
+}
+
+pub fn frame_all(sk: &mut Skelements) {
+    let all: Vec<usize> = (0..sk.armature.bones.len()).collect();
+    if let Some((min, max)) = bone_bounds(sk, &all) {
+        frame(&mut sk.camera, &min, &max, &sk.window_size);
+    }
+}
@@ -0,0 +1,150 @@
+use egui::*;
+
+use skelform_runtime::easing;
+
+use crate::animation;
+use crate::mq_backbone::{Interpolation, Skelements};
+
+#[rustfmt::skip]
+const INTERPOLATIONS: [(Interpolation, &str); 8] = [
+    (Interpolation::Step, "Step"),
+    (Interpolation::Linear, "Linear"),
+    (Interpolation::Bezier, "Bezier"),
+    (Interpolation::EaseIn, "Ease In"),
+    (Interpolation::EaseOut, "Ease Out"),
+    (Interpolation::EaseInOut, "Ease In-Out"),
+    (Interpolation::Back, "Back"),
+    (Interpolation::Elastic, "Elastic"),
+];
+
+const GRAPH_SIZE: f32 = 150.;
+
+// values shown in the graph, with some room for overshooting curves
+const GRAPH_MIN: f32 = -0.25;
+const GRAPH_MAX: f32 = 1.25;
+
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    egui::Window::new("Curve")
+        .movable(false)
+        .anchor(Align2::RIGHT_BOTTOM, Vec2 { x: -20., y: -10. })
+        .show(egui_ctx, |ui| {
+            let key = match skelements.selected_key {
+                Some(k) if skelements.selected_anim != usize::MAX => k,
+                _ => {
+                    ui.label("No keyframe selected");
+                    return;
+                }
+            };
+
+            // edits go to a copy, so only an actual change touches the animation
+            let anim = &skelements.armature.animations[skelements.selected_anim];
+            let found = animation::find_track(anim, key.bone_id)
+                .and_then(|t| animation::find_curve(t, key.prop, key.frame));
+            let mut curve = match found {
+                Some(c) => c.clone(),
+                None => return,
+            };
+            let curve = &mut curve;
+
+            let mut changed = false;
+
+            let selected_name = INTERPOLATIONS
+                .iter()
+                .find(|(i, _)| *i == curve.interp)
+                .unwrap()
+                .1;
+            ComboBox::from_label("Interpolation")
+                .selected_text(selected_name)
+                .show_ui(ui, |ui| {
+                    for (interp, name) in INTERPOLATIONS {
+                        changed |= ui
+                            .selectable_value(&mut curve.interp, interp, name)
+                            .changed();
+                    }
+                });
+
+            let (rect, _) = ui.allocate_exact_size(vec2(GRAPH_SIZE, GRAPH_SIZE), Sense::hover());
+
+            // normalized curve space to screen, and back
+            let to_screen = |x: f32, y: f32| {
+                let y = (y - GRAPH_MIN) / (GRAPH_MAX - GRAPH_MIN);
+                pos2(
+                    rect.left() + x * rect.width(),
+                    rect.bottom() - y * rect.height(),
+                )
+            };
+            let from_screen = |p: Pos2| {
+                let x = (p.x - rect.left()) / rect.width();
+                let y = (rect.bottom() - p.y) / rect.height();
+                (x, y * (GRAPH_MAX - GRAPH_MIN) + GRAPH_MIN)
+            };
+
+            let painter = ui.painter();
+            painter.rect_filled(rect, 0., Color32::from_rgb(30, 30, 30));
+
+            // lines marking the start and end values
+            let guide = Stroke::new(1., Color32::from_rgb(60, 60, 60));
+            painter.hline(rect.x_range(), to_screen(0., 0.).y, guide);
+            painter.hline(rect.x_range(), to_screen(0., 1.).y, guide);
+
+            // the curve itself
+            let mut points = vec![];
+            let samples = 64;
+            for i in 0..=samples {
+                let t = i as f32 / samples as f32;
+                points.push(to_screen(t, easing::ease(curve, t)));
+            }
+            painter.add(Shape::line(
+                points,
+                Stroke::new(2., Color32::from_rgb(230, 200, 60)),
+            ));
+
+            if curve.interp == Interpolation::Bezier {
+                // (handle index, the curve end it's attached to)
+                #[rustfmt::skip]
+                let handles = [
+                    (0, to_screen(0., 0.)),
+                    (2, to_screen(1., 1.)),
+                ];
+
+                for (h, anchor) in handles {
+                    let pos = to_screen(curve.handles[h], curve.handles[h + 1]);
+                    let handle_rect = Rect::from_center_size(pos, vec2(10., 10.));
+                    let response =
+                        ui.interact(handle_rect, ui.id().with(("handle", h)), Sense::drag());
+
+                    if response.dragged() {
+                        if let Some(pointer) = response.interact_pointer_pos() {
+                            let (x, y) = from_screen(pointer);
+                            curve.handles[h] = x.clamp(0., 1.);
+                            curve.handles[h + 1] = y.clamp(GRAPH_MIN, GRAPH_MAX);
+                            changed = true;
+                        }
+                    }
+
+                    let painter = ui.painter();
+                    painter.line_segment([anchor, pos], Stroke::new(1., Color32::GRAY));
+                    let col = if response.hovered() || response.dragged() {
+                        Color32::WHITE
+                    } else {
+                        Color32::LIGHT_GRAY
+                    };
+                    painter.circle_filled(pos, 4., col);
+                }
+            }
+
+            // re-pose so the change is visible right away
+            if changed {
+                skelements.posed_frame = -1.;
+
+                let before = skelements.armature.clone();
+                let anim = &mut skelements.armature.animations[skelements.selected_anim];
+                let track = animation::track_mut(anim, key.bone_id);
+                if let Some(c) = animation::curve_mut(track, key.prop, key.frame) {
+                    *c = curve.clone();
+                }
+                let after = skelements.armature.clone();
+                skelements.history.edit_armature(before, after, true);
+            }
+        });
+}
@@ -0,0 +1,108 @@
+use egui::{Align2, Button, Context, ScrollArea, Vec2};
+
+use crate::animation;
+use crate::mq_backbone::Skelements;
+use crate::selection;
+
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    // as shown, which an animation may have changed
+    let order = animation::draw_order(skelements);
+
+    // edits are applied after the window, so it's only
+    // cloned for undo when something actually changed
+    let mut swap: Option<(usize, usize)> = None;
+    let mut clicked: Option<usize> = None;
+    let mut jump_to: Option<i32> = None;
+    let (mut key, mut delete_key) = (false, false);
+
+    egui::Window::new("Draw Order")
+        .movable(false)
+        .anchor(Align2::RIGHT_BOTTOM, Vec2 { x: -20., y: -10. })
+        .show(egui_ctx, |ui| {
+            // front first, as that's what's on top
+            ScrollArea::vertical().max_height(150.).show(ui, |ui| {
+                for pos in (0..order.len()).rev() {
+                    let idx = order[pos];
+                    ui.horizontal(|ui| {
+                        let can_raise = pos + 1 < order.len();
+                        if ui.add_enabled(can_raise, Button::new("Up")).clicked() {
+                            swap = Some((pos, pos + 1));
+                        }
+                        if ui.add_enabled(pos > 0, Button::new("Down")).clicked() {
+                            swap = Some((pos, pos - 1));
+                        }
+                        let selected = selection::is_selected(skelements, idx);
+                        let name = &skelements.armature.bones[idx].name;
+                        if ui.selectable_label(selected, name).clicked() {
+                            clicked = Some(idx);
+                        }
+                    });
+                }
+            });
+
+            if skelements.selected_anim == usize::MAX {
+                return;
+            }
+
+            let anim = &skelements.armature.animations[skelements.selected_anim];
+            ui.horizontal(|ui| {
+                if ui.button("Key Order").clicked() {
+                    key = true;
+                }
+                let keyed = anim
+                    .draw_order
+                    .iter()
+                    .any(|k| k.frame == skelements.anim_frame);
+                if ui.add_enabled(keyed, Button::new("Delete Key")).clicked() {
+                    delete_key = true;
+                }
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Keys:");
+                for k in &anim.draw_order {
+                    if ui.small_button(k.frame.to_string()).clicked() {
+                        jump_to = Some(k.frame);
+                    }
+                }
+            });
+        });
+
+    if let Some(idx) = clicked {
+        selection::select(skelements, idx);
+    }
+    if let Some(frame) = jump_to {
+        skelements.playing = false;
+        skelements.anim_frame = frame;
+    }
+    if swap.is_none() && !key && !delete_key {
+        return;
+    }
+
+    // ids of every bone, including ones left out of `draw_order`
+    let mut ids: Vec<i32> = order
+        .iter()
+        .map(|i| skelements.armature.bones[*i].id)
+        .collect();
+    let before = skelements.armature.clone();
+    if let Some((a, b)) = swap {
+        ids.swap(a, b);
+    }
+
+    // while animating, moving bones keys the order
+    // on this frame rather than changing the setup's
+    let frame = skelements.anim_frame;
+    if swap.is_some() && skelements.selected_anim == usize::MAX {
+        skelements.armature.draw_order = ids;
+    } else if swap.is_some() || key {
+        let anim = &mut skelements.armature.animations[skelements.selected_anim];
+        animation::insert_key(&mut anim.draw_order, frame, ids);
+        skelements.posed_frame = -1.;
+    }
+    if delete_key {
+        let anim = &mut skelements.armature.animations[skelements.selected_anim];
+        anim.draw_order.retain(|k| k.frame != frame);
+        skelements.posed_frame = -1.;
+    }
+    let after = skelements.armature.clone();
+    skelements.history.edit_armature(before, after, false);
+}
@@ -0,0 +1,701 @@
+use std::f32::consts::PI;
+
+use miniquad::{RenderingBackend, TextureId};
+use skelform_runtime::armature::{bone_texture, bone_texture_mut, image_transform};
+use skelform_runtime::atlas::{bone_region, PIXEL_SIZE};
+
+use crate::animation;
+use crate::armature_window;
+use crate::batch::SpriteBatch;
+use crate::bindings::GpuCache;
+use crate::camera::{
+    screen_to_clip, screen_to_world, world_to_bone, world_to_parent, world_to_screen,
+};
+use crate::mesh;
+use crate::mq_backbone::{Bone, Skelements, Transform2D, Vec2, Vertex};
+use crate::selection;
+use crate::skins_window;
+use crate::utils::{dist_to_segment, in_triangle};
+
+// sizes on screen, in points
+const ARROW_LENGTH: f32 = 60.;
+const RING_RADIUS: f32 = 50.;
+const CORNER_DISTANCE: f32 = 40.;
+const HANDLE_SIZE: f32 = 5.; // half the size of corner squares and arrow heads
+const LINE_WIDTH: f32 = 2.;
+const GRAB_DISTANCE: f32 = 6.; // how far off a handle it can still be grabbed
+const BRUSH_RADIUS: f32 = 40.; // for painting weights
+
+// steps to snap to while holding shift
+const SNAP_DISTANCE: f32 = 0.05; // in world units
+const SNAP_ANGLE: f32 = PI / 12.;
+const SNAP_SCALE: f32 = 0.1;
+
+// for handles that stay upright on screen, like mesh vertices
+const SCREEN_AXES: [Vec2; 2] = [Vec2 { x: 1., y: 0. }, Vec2 { x: 0., y: 1. }];
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Handle {
+    #[default]
+    None,
+    Free, // the bone itself, rather than part of the gizmo
+    AxisX,
+    AxisY,
+    Ring,
+    Corner,
+    Pivot,         // the active bone's origin, moved without moving its texture
+    Vertex(usize), // one of the vertices of the active bone's mesh
+    Brush,         // paints weights onto the active bone's mesh
+}
+
+/// state of the selection when a drag started. every update works off of
+/// this rather than the last one, so snapping doesn't build up errors
+pub struct Drag {
+    handle: Handle,
+    roots: Vec<usize>,
+    start_bones: Vec<Bone>,
+    start_pos: Vec<Vec2>,        // world position of each root
+    start_axes: Vec<[Vec2; 2]>,  // world directions of each root's x and y
+    to_parent: Vec<Transform2D>, // world to parent space of each root
+    pivot: Vec2,
+    axes: [Vec2; 2],   // gizmo's x and y in world space
+    start_mouse: Vec2, // in world space
+}
+
+/// where the gizmo sits and which way it points, in world space. it's at the
+/// middle of the selection, and follows the active bone's orientation
+fn placement(sk: &Skelements) -> Option<(Vec2, [Vec2; 2])> {
+    let roots = selection::roots(sk);
+    let transforms = &sk.world_transforms;
+    if roots.is_empty()
+        || sk.selected_bone >= transforms.len()
+        || roots.iter().any(|r| *r >= transforms.len())
+    {
+        return None;
+    }
+
+    let mut pivot = Vec2::default();
+    for r in &roots {
+        let pos = transforms[*r].translation();
+        pivot.x += pos.x / roots.len() as f32;
+        pivot.y += pos.y / roots.len() as f32;
+    }
+
+    let active = &transforms[sk.selected_bone];
+    let (sin, cos) = active.rotation().sin_cos();
+    let mut y = Vec2 { x: -sin, y: cos };
+    if active.determinant() < 0. {
+        y = y.scale(-1.);
+    }
+    Some((pivot, [Vec2 { x: cos, y: sin }, y]))
+}
+
+/// gizmo placement in screen space, with axes as unit directions
+fn screen_placement(sk: &Skelements) -> Option<(Vec2, [Vec2; 2])> {
+    let (pivot, axes) = placement(sk)?;
+    let origin = world_to_screen(&pivot, &sk.camera, &sk.window_size);
+    let axis = |a: &Vec2| {
+        let tip = world_to_screen(&pivot.add(a), &sk.camera, &sk.window_size);
+        tip.sub(&origin).normalize()
+    };
+    let screen_axes = [axis(&axes[0]), axis(&axes[1])];
+    Some((origin, screen_axes))
+}
+
+/// where the pivot handle is on screen. only bones with a texture have one,
+/// since there's nothing to move the origin relative to otherwise. the pivot
+/// isn't animated, so it's only moved in the setup pose
+fn pivot_placement(sk: &Skelements) -> Option<Vec2> {
+    if sk.selected_anim != usize::MAX {
+        return None;
+    }
+    let bone = skins_window::skinned(sk, sk.armature.bones.get(sk.selected_bone)?);
+    bone_region(sk.atlas.as_ref()?, &bone)?;
+    let pos = sk.world_transforms.get(sk.selected_bone)?.translation();
+    Some(world_to_screen(&pos, &sk.camera, &sk.window_size))
+}
+
+/// part of the gizmo under the mouse, for the current `op_mode`
+pub fn hit(sk: &Skelements) -> Handle {
+    if sk.op_mode == 3 {
+        return match pivot_placement(sk) {
+            Some(p) if sk.mouse.sub(&p).length() <= HANDLE_SIZE * 2. + GRAB_DISTANCE => {
+                Handle::Pivot
+            }
+            _ => Handle::None,
+        };
+    }
+    if sk.op_mode == 4 {
+        return hit_vertex(sk);
+    }
+    if sk.op_mode == 5 {
+        return hit_brush(sk);
+    }
+
+    let (origin, axes) = match screen_placement(sk) {
+        Some(p) => p,
+        None => return Handle::None,
+    };
+    let mouse = &sk.mouse;
+
+    match sk.op_mode {
+        0 => {
+            for (axis, handle) in [(&axes[0], Handle::AxisX), (&axes[1], Handle::AxisY)] {
+                let tip = origin.add(&axis.scale(ARROW_LENGTH));
+                let head = arrow_head(&tip, axis, &sk.window_size);
+                if dist_to_segment(mouse, &origin, &tip) <= GRAB_DISTANCE
+                    || in_triangle(&screen_to_clip(mouse, &sk.window_size), &head)
+                {
+                    return handle;
+                }
+            }
+        }
+        1 if (mouse.sub(&origin).length() - RING_RADIUS).abs() <= GRAB_DISTANCE => {
+            return Handle::Ring;
+        }
+        2 => {
+            for c in corners(&origin, &axes) {
+                if mouse.sub(&c).length() <= HANDLE_SIZE + GRAB_DISTANCE {
+                    return Handle::Corner;
+                }
+            }
+        }
+        _ => {}
+    }
+    Handle::None
+}
+
+/// closest vertex of the active bone's mesh within reach of the mouse
+fn hit_vertex(sk: &Skelements) -> Handle {
+    let vertices = match mesh::world_vertices(sk, sk.selected_bone) {
+        Some(v) => v,
+        None => return Handle::None,
+    };
+    let screen: Vec<Vec2> = vertices
+        .iter()
+        .map(|v| world_to_screen(v, &sk.camera, &sk.window_size))
+        .collect();
+    let dist = |i: &usize| screen[*i].sub(&sk.mouse).length();
+    let closest = (0..screen.len()).min_by(|a, b| dist(a).total_cmp(&dist(b)));
+    match closest {
+        Some(i) if dist(&i) <= HANDLE_SIZE + GRAB_DISTANCE => Handle::Vertex(i),
+        _ => Handle::None,
+    }
+}
+
+/// the brush, which can paint anywhere as long as there's a mesh to paint
+/// and a bone to paint weights for. weights are only painted in the setup pose
+fn hit_brush(sk: &Skelements) -> Handle {
+    let painting =
+        sk.selected_anim == usize::MAX && sk.armature.bones.iter().any(|b| b.id == sk.weight_bone);
+    match mesh::world_vertices(sk, sk.selected_bone) {
+        Some(_) if painting => Handle::Brush,
+        _ => Handle::None,
+    }
+}
+
+/// start dragging the selection with a handle
+pub fn begin(sk: &mut Skelements, handle: Handle) {
+    let (pivot, axes) = match placement(sk) {
+        Some(p) => p,
+        None => return,
+    };
+    // moving the pivot also moves the active bone's children back,
+    // so they stay where they were. vertices and weights only change the active bone
+    let roots = if let Handle::Vertex(_) | Handle::Brush = handle {
+        vec![sk.selected_bone]
+    } else if handle == Handle::Pivot {
+        let mut bones = vec![sk.selected_bone];
+        let id = sk.armature.bones[sk.selected_bone].id;
+        for (i, b) in sk.armature.bones.iter().enumerate() {
+            if b.parent_id == id && i != sk.selected_bone {
+                bones.push(i);
+            }
+        }
+        bones
+    } else {
+        selection::roots(sk)
+    };
+
+    sk.drag = Some(Drag {
+        handle,
+        start_bones: roots
+            .iter()
+            .map(|r| animation::bones(sk)[*r].clone())
+            .collect(),
+        start_pos: roots
+            .iter()
+            .map(|r| sk.world_transforms[*r].translation())
+            .collect(),
+        start_axes: roots
+            .iter()
+            .map(|r| {
+                let w = &sk.world_transforms[*r];
+                let x = Vec2 { x: w.a, y: w.b };
+                let y = Vec2 { x: w.c, y: w.d };
+                [x.normalize(), y.normalize()]
+            })
+            .collect(),
+        to_parent: roots.iter().map(|r| world_to_parent(sk, *r)).collect(),
+        roots,
+        pivot,
+        axes,
+        start_mouse: screen_to_world(&sk.mouse, &sk.camera, &sk.window_size),
+    });
+}
+
+pub fn end(sk: &mut Skelements) {
+    sk.drag = None;
+}
+
+/// move, rotate or scale the dragged bones to follow the mouse, or paint
+/// weights with it. rotating and scaling are done around the gizmo's pivot
+pub fn update(sk: &mut Skelements, snap: bool) {
+    let drag = match &sk.drag {
+        Some(d) => d,
+        None => return,
+    };
+    let mouse = screen_to_world(&sk.mouse, &sk.camera, &sk.window_size);
+    let from_pivot = drag.start_mouse.sub(&drag.pivot);
+    let to_mouse = mouse.sub(&drag.pivot);
+    let [ax, ay] = &drag.axes;
+
+    let mut bones = drag.start_bones.clone();
+    match (drag.handle, sk.op_mode) {
+        (Handle::AxisX | Handle::AxisY, _) | (Handle::Free, 0) => {
+            let delta = mouse.sub(&drag.start_mouse);
+            let offset = match drag.handle {
+                Handle::AxisX | Handle::AxisY => {
+                    let axis = if drag.handle == Handle::AxisX { ax } else { ay };
+                    let mut t = delta.dot(axis);
+                    if snap {
+                        t = snap_to(t, SNAP_DISTANCE);
+                    }
+                    axis.scale(t)
+                }
+                _ if snap => Vec2 {
+                    x: snap_to(delta.x, SNAP_DISTANCE),
+                    y: snap_to(delta.y, SNAP_DISTANCE),
+                },
+                _ => delta,
+            };
+            for (i, b) in bones.iter_mut().enumerate() {
+                let target = drag.start_pos[i].add(&offset);
+                b.pos = drag.to_parent[i].transform_point(&target);
+            }
+        }
+
+        (Handle::Ring, _) | (Handle::Free, 1) => {
+            let mut angle = to_mouse.y.atan2(to_mouse.x) - from_pivot.y.atan2(from_pivot.x);
+            if snap {
+                angle = snap_to(angle, SNAP_ANGLE);
+            }
+            for (i, b) in bones.iter_mut().enumerate() {
+                let offset = drag.start_pos[i].sub(&drag.pivot).rotate(angle);
+                b.pos = drag.to_parent[i].transform_point(&drag.pivot.add(&offset));
+
+                // mirrored parents turn the other way
+                if drag.to_parent[i].determinant() < 0. {
+                    b.rot -= angle;
+                } else {
+                    b.rot += angle;
+                }
+            }
+        }
+
+        (Handle::Corner, _) | (Handle::Free, 2) => {
+            // in the gizmo's axes
+            let local = |v: &Vec2| Vec2 {
+                x: v.dot(ax),
+                y: v.dot(ay),
+            };
+            let (start, now) = (local(&from_pivot), local(&to_mouse));
+            let mut factor = if drag.handle == Handle::Corner {
+                Vec2 {
+                    x: ratio(now.x, start.x),
+                    y: ratio(now.y, start.y),
+                }
+            } else {
+                let r = ratio(to_mouse.length(), from_pivot.length());
+                Vec2 { x: r, y: r }
+            };
+            if snap {
+                factor.x = snap_to(factor.x, SNAP_SCALE);
+                factor.y = snap_to(factor.y, SNAP_SCALE);
+            }
+
+            // how much the gizmo's scale stretches a direction, for bones
+            // turned differently from the active one (whose axes it uses)
+            let stretch = |v: &Vec2| {
+                let g = local(v);
+                let scaled = ax.scale(g.x * factor.x).add(&ay.scale(g.y * factor.y));
+                scaled.dot(v)
+            };
+
+            for (i, b) in bones.iter_mut().enumerate() {
+                let offset = local(&drag.start_pos[i].sub(&drag.pivot));
+                let target = drag.pivot.add(
+                    &ax.scale(offset.x * factor.x)
+                        .add(&ay.scale(offset.y * factor.y)),
+                );
+                b.pos = drag.to_parent[i].transform_point(&target);
+                let [bone_x, bone_y] = &drag.start_axes[i];
+                b.scale.x *= stretch(bone_x);
+                b.scale.y *= stretch(bone_y);
+            }
+        }
+
+        (Handle::Pivot, _) => {
+            let mut delta = mouse.sub(&drag.start_mouse);
+            if snap {
+                delta.x = snap_to(delta.x, SNAP_DISTANCE);
+                delta.y = snap_to(delta.y, SNAP_DISTANCE);
+            }
+            let target = drag.start_pos[0].add(&delta);
+            bones[0].pos = drag.to_parent[0].transform_point(&target);
+
+            // the same move in the bone's own space, as it was before it
+            let local = world_to_bone(sk, drag.roots[0]).transform_vector(&delta);
+
+            // the skin decides which attachment's pivot is moved
+            let start_bone = skins_window::skinned(sk, &drag.start_bones[0]);
+            let own_attachment =
+                std::mem::replace(&mut bones[0].attachment, start_bone.attachment.clone());
+
+            // and in the image's space, which an attachment may rotate or scale
+            let to_image = image_transform(&start_bone)
+                .invert()
+                .unwrap_or(Transform2D::IDENTITY);
+            let in_image = to_image.transform_vector(&local);
+
+            // move the pivot along with the origin, so the image stays put
+            let region = sk
+                .atlas
+                .as_ref()
+                .and_then(|a| bone_region(a, &bones[0]).cloned())
+                .unwrap_or_default();
+            let start_pivot = bone_texture(&start_bone).pivot.clone();
+            let tex = bone_texture_mut(&mut bones[0]);
+            if region.original_width != 0 && region.original_height != 0 {
+                tex.pivot.x =
+                    start_pivot.x + in_image.x / (region.original_width as f32 * PIXEL_SIZE);
+                tex.pivot.y =
+                    start_pivot.y + in_image.y / (region.original_height as f32 * PIXEL_SIZE);
+            }
+            bones[0].attachment = own_attachment;
+
+            for b in bones.iter_mut().skip(1) {
+                b.pos = b.pos.sub(&local);
+            }
+        }
+
+        (Handle::Vertex(v), _) => {
+            let mut delta = mouse.sub(&drag.start_mouse);
+            if snap {
+                delta.x = snap_to(delta.x, SNAP_DISTANCE);
+                delta.y = snap_to(delta.y, SNAP_DISTANCE);
+            }
+
+            // the skin decides which attachment's mesh is edited
+            let start_bone = skins_window::skinned(sk, &drag.start_bones[0]);
+            let own_attachment =
+                std::mem::replace(&mut bones[0].attachment, start_bone.attachment.clone());
+
+            // weights may bend the mesh differently at each vertex
+            let to_image = mesh::vertex_to_world(sk, drag.roots[0], &start_bone, v)
+                .and_then(|t| t.invert())
+                .unwrap_or(Transform2D::IDENTITY);
+            let in_image = to_image.transform_vector(&delta);
+
+            if sk.selected_anim == usize::MAX {
+                // reshape the mesh itself, which is relative to the image's size
+                let region = sk
+                    .atlas
+                    .as_ref()
+                    .and_then(|a| bone_region(a, &bones[0]).cloned())
+                    .unwrap_or_default();
+                let tex = bone_texture_mut(&mut bones[0]);
+                if let Some(p) = tex.mesh.vertices.get_mut(v) {
+                    if region.original_width != 0 && region.original_height != 0 {
+                        p.x += in_image.x / (region.original_width as f32 * PIXEL_SIZE);
+                        p.y += in_image.y / (region.original_height as f32 * PIXEL_SIZE);
+                    }
+                }
+            } else {
+                // while animating, only offset it so the offsets can be keyed
+                let count = bone_texture(&bones[0]).mesh.vertices.len();
+                let deform = &mut bones[0].deform;
+                if deform.len() < count {
+                    deform.resize(count, Vec2::default());
+                }
+                if let Some(d) = deform.get_mut(v) {
+                    *d = d.add(&in_image);
+                }
+            }
+            bones[0].attachment = own_attachment;
+        }
+
+        (Handle::Brush, _) => {
+            let idx = drag.roots[0];
+            let paint_idx = armature_window::find_bone_idx(&sk.armature.bones, sk.weight_bone);
+            if paint_idx == -1 {
+                return;
+            }
+
+            // strokes build up as the brush moves, so they
+            // work off the bone as it is now rather than at the start
+            bones[0] = sk.armature.bones[idx].clone();
+            let vertices = mesh::world_vertices(sk, idx).unwrap_or_default();
+            let owner_id = bones[0].id;
+
+            // the skin decides which attachment's mesh is painted
+            let shown = skins_window::skinned(sk, &bones[0]);
+            let own_attachment = std::mem::replace(&mut bones[0].attachment, shown.attachment);
+            let mesh = &mut bone_texture_mut(&mut bones[0]).mesh;
+            mesh::bind_bone(sk, idx, mesh, paint_idx as usize);
+            for (i, v) in vertices.iter().enumerate() {
+                let screen = world_to_screen(v, &sk.camera, &sk.window_size);
+                let d = screen.sub(&sk.mouse).length();
+                if d > BRUSH_RADIUS {
+                    continue;
+                }
+
+                // fading out towards the edge of the brush. shift,
+                // which snaps when dragging, takes weight away instead
+                let target = sk.brush_strength * (1. - d / BRUSH_RADIUS);
+                let current = mesh::weight(mesh, i, sk.weight_bone, owner_id);
+                let weight = if snap {
+                    current.min(1. - target)
+                } else {
+                    current.max(target)
+                };
+                if weight != current {
+                    mesh::set_weight(mesh, i, sk.weight_bone, weight, owner_id);
+                }
+            }
+            bones[0].attachment = own_attachment;
+        }
+        _ => return,
+    }
+
+    let roots = drag.roots.clone();
+    let before = drag.start_bones.clone();
+    for (r, b) in roots.iter().zip(&bones) {
+        animation::bones_mut(sk)[*r] = b.clone();
+    }
+
+    // the whole drag is a single undo. poses are undone by keying
+    if sk.selected_anim == usize::MAX {
+        sk.history.edit_bones(roots, before, bones, true);
+    }
+}
+
+/// render the gizmo for the current `op_mode`
+pub fn draw(
+    sk: &Skelements,
+    mq_ctx: &mut Box<dyn RenderingBackend>,
+    cache: &mut GpuCache,
+    batch: &mut SpriteBatch,
+) {
+    let (origin, axes) = match screen_placement(sk) {
+        Some(p) => p,
+        None => return,
+    };
+    let ws = &sk.window_size;
+
+    // highlight whatever is being dragged, or could be
+    let active = match &sk.drag {
+        Some(d) => d.handle,
+        None => sk.gizmo_hover,
+    };
+
+    // mesh edges, which can't be grabbed on their own
+    let wire = cache.color(mq_ctx, [200, 200, 200, 160]);
+    let mut color = |handle: Handle, col: [u8; 4]| {
+        let col = if active == handle {
+            [255, 255, 160, 255]
+        } else {
+            col
+        };
+        cache.color(mq_ctx, col)
+    };
+
+    let mut shapes: Vec<(TextureId, Vec<Vertex>)> = vec![];
+    match sk.op_mode {
+        0 => {
+            #[rustfmt::skip]
+            let arrows = [
+                (&axes[0], Handle::AxisX, [220, 70, 70, 255]),
+                (&axes[1], Handle::AxisY, [70, 200, 70, 255]),
+            ];
+            for (axis, handle, col) in arrows {
+                let image = color(handle, col);
+                let tip = origin.add(&axis.scale(ARROW_LENGTH));
+                shapes.push((image, line(&origin, &tip, ws)));
+                shapes.push((image, arrow_head(&tip, axis, ws)));
+            }
+        }
+        1 => {
+            let image = color(Handle::Ring, [70, 130, 230, 255]);
+            for l in circle(&origin, RING_RADIUS, ws) {
+                shapes.push((image, l));
+            }
+        }
+        2 => {
+            let image = color(Handle::Corner, [230, 160, 50, 255]);
+            for c in corners(&origin, &axes) {
+                shapes.push((image, square(&c, &axes, ws)));
+            }
+        }
+        3 => {
+            // crosshair, so it's clear exactly where the origin is
+            if let Some(p) = pivot_placement(sk) {
+                let image = color(Handle::Pivot, [230, 230, 230, 255]);
+                let size = HANDLE_SIZE * 2.;
+                for d in [Vec2 { x: size, y: 0. }, Vec2 { x: 0., y: size }] {
+                    shapes.push((image, line(&p.sub(&d), &p.add(&d), ws)));
+                }
+            }
+        }
+        4 => {
+            let screen = mesh_wire(sk, wire, &mut shapes);
+            for (i, p) in screen.iter().enumerate() {
+                let image = color(Handle::Vertex(i), [230, 120, 200, 255]);
+                shapes.push((image, square(p, &SCREEN_AXES, ws)));
+            }
+        }
+        5 => {
+            // vertices go from blue with none of the painted bone's weight
+            // to red with all of it, in steps so few colors get cached
+            let screen = mesh_wire(sk, wire, &mut shapes);
+            let bone = skins_window::skinned(sk, &animation::bones(sk)[sk.selected_bone]);
+            let mesh = &bone_texture(&bone).mesh;
+            for (i, p) in screen.iter().enumerate() {
+                let w = (mesh::weight(mesh, i, sk.weight_bone, bone.id) * 10.).round() / 10.;
+                let col = [(40. + 190. * w) as u8, 60, (230. - 190. * w) as u8, 255];
+                shapes.push((cache.color(mq_ctx, col), square(p, &SCREEN_AXES, ws)));
+            }
+            if active == Handle::Brush {
+                let image = cache.color(mq_ctx, [255, 255, 160, 255]);
+                for l in circle(&sk.mouse, BRUSH_RADIUS, ws) {
+                    shapes.push((image, l));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for (image, verts) in shapes {
+        if verts.len() == 3 {
+            batch.tri(mq_ctx, image, &verts);
+        } else {
+            batch.quad(mq_ctx, image, &verts);
+        }
+    }
+}
+
+/// wireframe of the active bone's mesh, giving where its vertices are on screen
+fn mesh_wire(
+    sk: &Skelements,
+    image: TextureId,
+    shapes: &mut Vec<(TextureId, Vec<Vertex>)>,
+) -> Vec<Vec2> {
+    let vertices = mesh::world_vertices(sk, sk.selected_bone).unwrap_or_default();
+    let screen: Vec<Vec2> = vertices
+        .iter()
+        .map(|v| world_to_screen(v, &sk.camera, &sk.window_size))
+        .collect();
+    let bone = skins_window::skinned(sk, &animation::bones(sk)[sk.selected_bone]);
+    for (a, b) in mesh::edges(&bone_texture(&bone).mesh) {
+        if a < screen.len() && b < screen.len() {
+            shapes.push((image, line(&screen[a], &screen[b], &sk.window_size)));
+        }
+    }
+    screen
+}
+
+fn corners(origin: &Vec2, axes: &[Vec2; 2]) -> [Vec2; 4] {
+    [(1., 1.), (-1., 1.), (-1., -1.), (1., -1.)].map(|(x, y)| {
+        origin.add(
+            &axes[0]
+                .scale(x * CORNER_DISTANCE)
+                .add(&axes[1].scale(y * CORNER_DISTANCE)),
+        )
+    })
+}
+
+// shapes are built in screen space, then converted to clip space
+
+fn vertex(p: &Vec2, window_size: &Vec2) -> Vertex {
+    Vertex {
+        pos: screen_to_clip(p, window_size),
+        uv: Vec2::default(),
+    }
+}
+
+/// quad for a line between two points on screen, at a constant width
+pub fn line(a: &Vec2, b: &Vec2, window_size: &Vec2) -> Vec<Vertex> {
+    let dir = b.sub(a).normalize();
+    let side = Vec2 {
+        x: -dir.y,
+        y: dir.x,
+    }
+    .scale(LINE_WIDTH / 2.);
+    [a.add(&side), b.add(&side), b.sub(&side), a.sub(&side)]
+        .iter()
+        .map(|p| vertex(p, window_size))
+        .collect()
+}
+
+/// lines around a circle on screen
+fn circle(center: &Vec2, radius: f32, window_size: &Vec2) -> Vec<Vec<Vertex>> {
+    let segments = 48;
+    let point = |i: i32| {
+        let angle = i as f32 / segments as f32 * PI * 2.;
+        Vec2 {
+            x: center.x + angle.cos() * radius,
+            y: center.y + angle.sin() * radius,
+        }
+    };
+    (0..segments)
+        .map(|i| line(&point(i), &point(i + 1), window_size))
+        .collect()
+}
+
+fn arrow_head(tip: &Vec2, dir: &Vec2, window_size: &Vec2) -> Vec<Vertex> {
+    let side = Vec2 {
+        x: -dir.y,
+        y: dir.x,
+    }
+    .scale(HANDLE_SIZE);
+    let front = tip.add(&dir.scale(HANDLE_SIZE * 2.));
+    [tip.add(&side), front, tip.sub(&side)]
+        .iter()
+        .map(|p| vertex(p, window_size))
+        .collect()
+}
+
+fn square(center: &Vec2, axes: &[Vec2; 2], window_size: &Vec2) -> Vec<Vertex> {
+    let x = axes[0].scale(HANDLE_SIZE);
+    let y = axes[1].scale(HANDLE_SIZE);
+    [
+        center.sub(&x).sub(&y),
+        center.add(&x).sub(&y),
+        center.add(&x).add(&y),
+        center.sub(&x).add(&y),
+    ]
+    .iter()
+    .map(|p| vertex(p, window_size))
+    .collect()
+}
+
+fn ratio(a: f32, b: f32) -> f32 {
+    if b.abs() < f32::EPSILON {
+        return 1.;
+    }
+    a / b
+}
+
+fn snap_to(value: f32, step: f32) -> f32 {
+    (value / step).round() * step
+}
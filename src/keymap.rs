@@ -0,0 +1,249 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use miniquad::{KeyCode, KeyMods};
+use serde::{Deserialize, Serialize};
+
+/// file the keymap is read from, in `config_dir`. it's written with
+/// the defaults if it doesn't exist, so there's something to edit
+pub const KEYMAP_FILE: &str = "keymap.json";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Translate,
+    Rotate,
+    Scale,
+    Pivot,
+    Mesh,
+    Weights,
+    DeleteBone,
+    NewBone,
+    Deselect,
+    Undo,
+    Redo,
+    FrameAll,
+    FrameSelected,
+}
+
+/// a key along with the modifiers that have to be held for it.
+/// `ctrl` also accepts cmd, so the same keymap works on macOS
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Shortcut {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn matches(&self, key: KeyCode, mods: &KeyMods) -> bool {
+        self.key == key
+            && self.ctrl == (mods.ctrl || mods.logo)
+            && self.shift == mods.shift
+            && self.alt == mods.alt
+    }
+}
+
+#[derive(Default)]
+pub struct Keymap {
+    pub bindings: Vec<(Shortcut, Action)>,
+}
+
+impl Keymap {
+    pub fn action(&self, key: KeyCode, mods: &KeyMods) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(s, _)| s.matches(key, mods))
+            .map(|(_, a)| *a)
+    }
+}
+
+/// keymap as it's written in the file, with shortcuts like "Ctrl+Shift+Z".
+/// actions left out of the file keep their default, and empty ones are unbound
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct KeymapFile {
+    translate: String,
+    rotate: String,
+    scale: String,
+    pivot: String,
+    mesh: String,
+    weights: String,
+    delete_bone: String,
+    new_bone: String,
+    deselect: String,
+    undo: String,
+    redo: String,
+    frame_all: String,
+    frame_selected: String,
+}
+
+impl Default for KeymapFile {
+    fn default() -> Self {
+        KeymapFile {
+            translate: "G".to_string(),
+            rotate: "R".to_string(),
+            scale: "S".to_string(),
+            pivot: "P".to_string(),
+            mesh: "M".to_string(),
+            weights: "W".to_string(),
+            delete_bone: "Delete".to_string(),
+            new_bone: "N".to_string(),
+            deselect: "Escape".to_string(),
+            undo: "Ctrl+Z".to_string(),
+            redo: "Ctrl+Shift+Z".to_string(),
+            frame_all: "Home".to_string(),
+            frame_selected: "F".to_string(),
+        }
+    }
+}
+
+/// where settings are kept, so they're found no matter which directory
+/// the editor was started from. falls back to that without a home
+pub fn config_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|h| PathBuf::from(h).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+    };
+    match base {
+        Some(b) => b.join("skelform"),
+        None => PathBuf::from("."),
+    }
+}
+
+/// read the keymap file, creating it first if needed. falls back to
+/// the defaults if it can't be read. problems with it, like shortcuts
+/// bound to more than one action, are returned to show the user
+pub fn load() -> (Keymap, Vec<String>) {
+    let mut warnings = vec![];
+    let file = match read(&config_dir().join(KEYMAP_FILE)) {
+        Ok(f) => f,
+        Err(e) => {
+            warnings.push(format!("Could not read keymap, using defaults: {}", e));
+            KeymapFile::default()
+        }
+    };
+
+    #[rustfmt::skip]
+    let entries = [
+        (&file.translate, Action::Translate),
+        (&file.rotate, Action::Rotate),
+        (&file.scale, Action::Scale),
+        (&file.pivot, Action::Pivot),
+        (&file.mesh, Action::Mesh),
+        (&file.weights, Action::Weights),
+        (&file.delete_bone, Action::DeleteBone),
+        (&file.new_bone, Action::NewBone),
+        (&file.deselect, Action::Deselect),
+        (&file.undo, Action::Undo),
+        (&file.redo, Action::Redo),
+        (&file.frame_all, Action::FrameAll),
+        (&file.frame_selected, Action::FrameSelected),
+    ];
+
+    let mut keymap = Keymap::default();
+    for (shortcut, action) in entries {
+        if shortcut.is_empty() {
+            continue;
+        }
+        let s = match parse_shortcut(shortcut) {
+            Some(s) => s,
+            None => {
+                warnings.push(format!("Unknown shortcut '{}' for {:?}", shortcut, action));
+                continue;
+            }
+        };
+
+        // the earlier action keeps it
+        match keymap.bindings.iter().find(|(b, _)| *b == s) {
+            Some((_, taken)) => warnings.push(format!(
+                "'{}' is bound to both {:?} and {:?}, so only {:?} uses it",
+                shortcut, taken, action, taken
+            )),
+            None => keymap.bindings.push((s, action)),
+        }
+    }
+    (keymap, warnings)
+}
+
+fn read(path: &PathBuf) -> Result<KeymapFile, String> {
+    if !fs::exists(path).map_err(|e| e.to_string())? {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json =
+            serde_json::to_string_pretty(&KeymapFile::default()).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())?;
+    }
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+/// parse shortcuts like "G" or "Ctrl+Shift+Z". modifiers are case-insensitive
+pub fn parse_shortcut(text: &str) -> Option<Shortcut> {
+    let mut shortcut = Shortcut {
+        key: KeyCode::Unknown,
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+
+    let parts: Vec<&str> = text.split('+').map(|p| p.trim()).collect();
+    let (key, mods) = parts.split_last()?;
+    for m in mods {
+        match m.to_lowercase().as_str() {
+            "ctrl" | "cmd" => shortcut.ctrl = true,
+            "shift" => shortcut.shift = true,
+            "alt" => shortcut.alt = true,
+            _ => return None,
+        }
+    }
+
+    shortcut.key = key_from_name(key)?;
+    Some(shortcut)
+}
+
+#[rustfmt::skip]
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    let key = match name.to_lowercase().as_str() {
+        "a" => KeyCode::A, "b" => KeyCode::B, "c" => KeyCode::C, "d" => KeyCode::D,
+        "e" => KeyCode::E, "f" => KeyCode::F, "g" => KeyCode::G, "h" => KeyCode::H,
+        "i" => KeyCode::I, "j" => KeyCode::J, "k" => KeyCode::K, "l" => KeyCode::L,
+        "m" => KeyCode::M, "n" => KeyCode::N, "o" => KeyCode::O, "p" => KeyCode::P,
+        "q" => KeyCode::Q, "r" => KeyCode::R, "s" => KeyCode::S, "t" => KeyCode::T,
+        "u" => KeyCode::U, "v" => KeyCode::V, "w" => KeyCode::W, "x" => KeyCode::X,
+        "y" => KeyCode::Y, "z" => KeyCode::Z,
+        "0" => KeyCode::Key0, "1" => KeyCode::Key1, "2" => KeyCode::Key2,
+        "3" => KeyCode::Key3, "4" => KeyCode::Key4, "5" => KeyCode::Key5,
+        "6" => KeyCode::Key6, "7" => KeyCode::Key7, "8" => KeyCode::Key8,
+        "9" => KeyCode::Key9,
+        "f1" => KeyCode::F1, "f2" => KeyCode::F2, "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4, "f5" => KeyCode::F5, "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7, "f8" => KeyCode::F8, "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10, "f11" => KeyCode::F11, "f12" => KeyCode::F12,
+        "escape" | "esc" => KeyCode::Escape,
+        "delete" | "del" => KeyCode::Delete,
+        "backspace" => KeyCode::Backspace,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Space,
+        "tab" => KeyCode::Tab,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        _ => return None,
+    };
+    Some(key)
+}
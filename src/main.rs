@@ -4,20 +4,47 @@ use egui::{menu, Context, TopBottomPanel, InputState};
 use miniquad as mq;
 use mq::*;
 
+mod animation;
 mod armature_window;
+mod batch;
 mod bindings;
 mod bone_window;
+mod camera;
+mod curve_window;
+mod draw_order_window;
+mod gizmo;
+mod keymap;
+mod mesh;
+mod messages_window;
 mod mq_backbone;
 mod operation_window;
+mod project;
+mod selection;
+mod skeleton;
+mod skins_window;
+mod stats_window;
+mod timeline_window;
 mod top_menu;
+mod undo;
 mod utils;
 
 use bindings::*;
-use mq_backbone::{add_image, Bone, Skelements, Stage, Vec2, Vertex};
-use utils::{in_bounding_box, rotate};
+use camera::{screen_to_clip, view_transform};
+use gizmo::Handle;
+use keymap::Action;
+use mq_backbone::{add_image, pack_textures, Bone, Skelements, Stage, Vec2, Vertex};
+use skelform_runtime::armature::{bone_texture, image_transform, world_transforms};
+use skelform_runtime::atlas::bone_region;
+use utils::hits_sprite;
 
 impl mq::EventHandler for mq_backbone::Stage {
-    fn update(&mut self) {}
+    fn update(&mut self) {
+        let now = mq::date::now();
+        let dt = (now - self.skelements.last_update) as f32;
+        self.skelements.last_update = now;
+
+        animation::update_playback(&mut self.skelements, dt);
+    }
 
     fn draw(&mut self) {
         self.mq_ctx
@@ -31,6 +58,8 @@ impl mq::EventHandler for mq_backbone::Stage {
             draw_ui(egui_ctx, &mut self.skelements);
         });
         draw_mq(self);
+        self.batch.flush(&mut self.mq_ctx);
+        self.skelements.render_stats = self.batch.stats;
 
         self.mq_ctx.end_render_pass();
         self.egui_mq.draw(&mut *self.mq_ctx);
@@ -40,78 +69,110 @@ impl mq::EventHandler for mq_backbone::Stage {
 
     fn mouse_motion_event(&mut self, x: f32, y: f32) {
         self.egui_mq.mouse_motion_event(x, y);
+        let egui_ctx = self.egui_mq.egui_ctx();
         let sk = &mut self.skelements;
 
-        if sk.mouse_pressed_frames < 5 || self.egui_mq.egui_ctx().is_pointer_over_area() {
+        // mouse is tracked here rather than once a frame,
+        // so nothing lags behind it
+        sk.mouse_prev = sk.mouse.clone();
+        sk.mouse = camera::window_to_screen(x, y, egui_ctx.pixels_per_point());
+
+        if sk.panning {
+            camera::pan(&mut sk.camera, &sk.mouse_prev, &sk.mouse, &sk.window_size);
             return;
         }
 
-        let sens_reduce = 100.;
-        let mouse_vel = Vec2 {
-            x: (sk.mouse.x - sk.mouse_prev.x) / sens_reduce,
-            y: (sk.mouse.y - sk.mouse_prev.y) / sens_reduce,
-        };
-        if sk.selected_bone == usize::MAX {
-            sk.camera.pos.x -= mouse_vel.x;
-            sk.camera.pos.y += mouse_vel.y;
-        } else {
-            let bone = &mut sk.armature.bones[sk.selected_bone];
-            match sk.op_mode {
-                // translate
-                0 => {
-                    // counteract parent's rotation
-                    // so that it's back to straight up and right
-                    let offset = rotate(&mouse_vel, bone.parent_rot);
-
-                    bone.pos.x += offset.x;
-                    bone.pos.y -= offset.y;
-                }
-
-                // rotate
-                1 => bone.rot -= mouse_vel.x,
-
-                // scale
-                2 => {
-                    bone.scale.x = f32::max(bone.scale.x + mouse_vel.x, 0.);
-                    bone.scale.y = f32::max(bone.scale.y - mouse_vel.y, 0.);
-                }
-                _ => {}
-            }
+        if sk.mouse_pressed_frames < 5 || egui_ctx.is_pointer_over_area() {
+            return;
         }
+
+        let snap = egui_ctx.input(|i| i.modifiers.shift);
+        gizmo::update(sk, snap);
     }
 
     fn mouse_wheel_event(&mut self, dx: f32, dy: f32) {
         self.egui_mq.mouse_wheel_event(dx, dy);
+        if self.egui_mq.egui_ctx().is_pointer_over_area() {
+            return;
+        }
+
+        // trackpads send many small deltas, rather than one per notch
+        let sk = &mut self.skelements;
+        let zoom = sk.camera.zoom * camera::ZOOM_STEP.powf(dy.clamp(-1., 1.));
+        camera::zoom_at(&mut sk.camera, &sk.mouse, zoom, &sk.window_size);
     }
 
     fn mouse_button_down_event(&mut self, mb: mq::MouseButton, x: f32, y: f32) {
         self.skelements.mouse_pressed = true;
         self.egui_mq.mouse_button_down_event(mb, x, y);
+        let egui_ctx = self.egui_mq.egui_ctx();
         let sk = &mut self.skelements;
+        sk.mouse = camera::window_to_screen(x, y, egui_ctx.pixels_per_point());
+        sk.mouse_prev = sk.mouse.clone();
+
+        if egui_ctx.is_pointer_over_area() {
+            return;
+        }
+        if mb == MouseButton::Right
+            || mb == MouseButton::Middle
+            || (mb == MouseButton::Left && sk.space_held)
+        {
+            sk.panning = true;
+            return;
+        }
 
-        // immediately select hovered bone if nothing else is
-        if sk.hovered_bone != -1 && sk.selected_bone == usize::MAX {
-            sk.selected_bone =
-                armature_window::find_bone_idx(&sk.armature.bones, sk.hovered_bone) as usize;
+        // pressing on an unselected bone selects it right away, so it
+        // can be dragged. dragging from empty space selects a rectangle
+        if sk.gizmo_hover != Handle::None {
+            gizmo::begin(sk, sk.gizmo_hover);
+        } else if sk.hovered_bone == -1 {
+            sk.marquee_start = Some(sk.mouse.clone());
+        } else {
+            let idx = armature_window::find_bone_idx(&sk.armature.bones, sk.hovered_bone) as usize;
+            if !selection::is_selected(sk, idx) && !egui_ctx.input(|i| i.modifiers.shift) {
+                selection::select(sk, idx);
+            }
+            gizmo::begin(sk, Handle::Free);
         }
     }
 
     fn mouse_button_up_event(&mut self, mb: mq::MouseButton, x: f32, y: f32) {
         self.skelements.mouse_pressed = false;
         self.egui_mq.mouse_button_up_event(mb, x, y);
+        let egui_ctx = self.egui_mq.egui_ctx();
         let sk = &mut self.skelements;
+        let shift = egui_ctx.input(|i| i.modifiers.shift);
 
-        // ignore if mouse is on UI or is dragging
-        if self.egui_mq.egui_ctx().is_pointer_over_area() || sk.mouse_pressed_frames > 5 {
+        let was_panning = sk.panning;
+        sk.panning = false;
+        gizmo::end(sk);
+        let marquee_start = sk.marquee_start.take();
+
+        // ignore if mouse is on UI
+        if was_panning || egui_ctx.is_pointer_over_area() {
+            return;
+        }
+
+        if sk.mouse_pressed_frames > 5 {
+            if let Some(start) = marquee_start {
+                select_in_rect(sk, &start, shift);
+            }
             return;
         }
 
-        // select hovered bone, or none if nothing's hovered
+        // clicking selects the hovered bone, or adds or removes it with shift.
+        // clicking on nothing deselects everything
         if sk.hovered_bone == -1 {
-            sk.selected_bone = usize::MAX;
+            if !shift {
+                selection::clear(sk);
+            }
         } else {
-            sk.selected_bone =
-                armature_window::find_bone_idx(&sk.armature.bones, sk.hovered_bone) as usize;
+            let idx = armature_window::find_bone_idx(&sk.armature.bones, sk.hovered_bone) as usize;
+            if shift {
+                selection::toggle(sk, idx);
+            } else {
+                selection::select(sk, idx);
+            }
         }
     }
 
@@ -121,15 +182,94 @@ impl mq::EventHandler for mq_backbone::Stage {
 
     fn key_down_event(&mut self, keycode: mq::KeyCode, keymods: mq::KeyMods, _repeat: bool) {
         self.egui_mq.key_down_event(keycode, keymods);
+
+        // leave keys to text fields being typed in
+        if self.egui_mq.egui_ctx().wants_keyboard_input() {
+            return;
+        }
+
+        let sk = &mut self.skelements;
+        if keycode == KeyCode::Space {
+            sk.space_held = true;
+        }
+        let action = match sk.keymap.action(keycode, &keymods) {
+            Some(a) => a,
+            None => return,
+        };
+        match action {
+            Action::Translate => sk.op_mode = 0,
+            Action::Rotate => sk.op_mode = 1,
+            Action::Scale => sk.op_mode = 2,
+            Action::Pivot => sk.op_mode = 3,
+            Action::Mesh => sk.op_mode = 4,
+            Action::Weights => sk.op_mode = 5,
+            Action::DeleteBone => armature_window::delete_selected_bones(sk),
+            Action::NewBone => armature_window::new_bone(sk),
+            Action::Deselect => selection::clear(sk),
+            Action::Undo => undo::undo(sk),
+            Action::Redo => undo::redo(sk),
+            Action::FrameAll => camera::frame_all(sk),
+            Action::FrameSelected => camera::frame_selected(sk),
+        }
     }
 
     fn key_up_event(&mut self, keycode: mq::KeyCode, keymods: mq::KeyMods) {
         self.egui_mq.key_up_event(keycode, keymods);
+        if keycode == KeyCode::Space {
+            self.skelements.space_held = false;
+        }
+    }
+}
+
+/// select bones whose origins are in the rectangle between `start`
+/// and the mouse, adding to the current selection if `add` is set
+fn select_in_rect(sk: &mut Skelements, start: &Vec2, add: bool) {
+    if !add {
+        selection::clear(sk);
+    }
+
+    let min = screen_to_clip(
+        &Vec2 {
+            x: f32::min(start.x, sk.mouse.x),
+            y: f32::max(start.y, sk.mouse.y),
+        },
+        &sk.window_size,
+    );
+    let max = screen_to_clip(
+        &Vec2 {
+            x: f32::max(start.x, sk.mouse.x),
+            y: f32::min(start.y, sk.mouse.y),
+        },
+        &sk.window_size,
+    );
+
+    let view = view_transform(&sk.camera, &sk.window_size);
+    for i in 0..sk.world_transforms.len().min(sk.armature.bones.len()) {
+        let pos = view.compose(&sk.world_transforms[i]).translation();
+        if pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y {
+            selection::add(sk, i);
+        }
     }
 }
 
 /// read temporary files created from file dialogs
 fn read_temp_file(skelements: &mut Skelements) {
+    if let Some(path) = take_temp_file(".skelform_open_path") {
+        if let Err(e) = project::open(&path, skelements) {
+            skelements.messages.push(format!("Could not open project: {}", e));
+        }
+    }
+    if let Some(path) = take_temp_file(".skelform_save_path") {
+        if let Err(e) = project::save(&path, skelements) {
+            skelements.messages.push(format!("Could not save project: {}", e));
+        }
+    }
+    if let Some(path) = take_temp_file(".skelform_export_path") {
+        if let Err(e) = project::export(&path, skelements) {
+            skelements.messages.push(format!("Could not export: {}", e));
+        }
+    }
+
     if !fs::exists(".skelform_img_path").unwrap() {
         return;
     }
@@ -140,11 +280,46 @@ fn read_temp_file(skelements: &mut Skelements) {
         return;
     }
 
-    add_image(fs, &mut skelements.textures);
+    // the image is for the bone the dialog was opened for, which may be gone
+    // by now. it's either for one of the bone's attachments, or the bone itself
+    let bone_idx = take_temp_file(".skelform_bone_idx").and_then(|i| i.parse().ok());
+    let attachment = take_temp_file(".skelform_attachment_idx").and_then(|i| i.parse().ok());
+    del_temp_files();
+    let bone_idx: usize = match bone_idx.filter(|i| *i < skelements.armature.bones.len()) {
+        Some(i) => i,
+        None => return,
+    };
 
-    skelements.armature.bones[skelements.selected_bone].tex.idx = skelements.textures.len() - 1;
+    if let Err(e) = add_image(fs.clone(), skelements) {
+        skelements
+            .messages
+            .push(format!("Could not load image '{}': {}", fs, e));
+        return;
+    }
 
-    del_temp_files();
+    let tex_idx = skelements.textures.len() - 1;
+    let before = skelements.armature.bones[bone_idx].clone();
+    let bone = &mut skelements.armature.bones[bone_idx];
+    match attachment.and_then(|i: usize| bone.attachments.get_mut(i)) {
+        Some(a) => a.tex.idx = tex_idx,
+        None => bone.tex.idx = tex_idx,
+    }
+    let after = skelements.armature.bones[bone_idx].clone();
+    skelements.history.edit_bone(bone_idx, before, after, false);
+    animation::repose(skelements);
+}
+
+/// get the contents of a temporary file and remove it
+fn take_temp_file(name: &str) -> Option<String> {
+    if !fs::exists(name).unwrap() {
+        return None;
+    }
+    let contents = fs::read_to_string(name).unwrap();
+    fs::remove_file(name).unwrap();
+    if contents.is_empty() {
+        return None;
+    }
+    Some(contents)
 }
 
 fn del_temp_files() {
@@ -165,25 +340,23 @@ fn draw_ui(egui_ctx: &Context, skelements: &mut Skelements) {
 
     read_temp_file(skelements);
 
-    top_menu::draw(&egui_ctx);
+    top_menu::draw(egui_ctx, skelements);
     armature_window::draw_armature(egui_ctx, skelements);
     bone_window::draw_bone(egui_ctx, skelements);
     operation_window::draw(egui_ctx, skelements);
+    timeline_window::draw(egui_ctx, skelements);
+    curve_window::draw(egui_ctx, skelements);
+    stats_window::draw(egui_ctx, skelements);
+    draw_order_window::draw(egui_ctx, skelements);
+    skins_window::draw(egui_ctx, skelements);
+    messages_window::draw(egui_ctx, skelements);
 
     egui_ctx.input(|i| {
-        if let Some(m) = i.pointer.hover_pos() {
-            // get window size
-            skelements.window_size = Vec2 {
-                x: i.screen_rect().size().x,
-                y: i.screen_rect().size().y,
-            };
-
-            // get mouse data
-            skelements.mouse_prev = skelements.mouse.clone();
-            skelements.mouse = Vec2 { x: m.x, y: m.y };
-        }
-
-        println!("{}", i.raw_scroll_delta);
+        // in points, same as the mouse
+        skelements.window_size = Vec2 {
+            x: i.screen_rect().size().x,
+            y: i.screen_rect().size().y,
+        };
     });
 
     if skelements.mouse_pressed {
@@ -192,6 +365,35 @@ fn draw_ui(egui_ctx: &Context, skelements: &mut Skelements) {
         skelements.mouse_pressed_frames = 0;
     }
 
+    // rubber-band selection
+    if let Some(start) = &skelements.marquee_start {
+        if skelements.mouse_pressed_frames > 5 {
+            let rect = egui::Rect::from_two_pos(
+                egui::pos2(start.x, start.y),
+                egui::pos2(skelements.mouse.x, skelements.mouse.y),
+            );
+            let painter = egui_ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Background,
+                "marquee".into(),
+            ));
+            painter.rect_filled(
+                rect,
+                0.,
+                egui::Color32::from_rgba_unmultiplied(120, 170, 255, 30),
+            );
+            painter.rect_stroke(
+                rect,
+                0.,
+                egui::Stroke::new(1., egui::Color32::from_rgb(120, 170, 255)),
+            );
+        }
+    }
+
+    // drags and typing are over, so the next edit gets its own undo step
+    if !skelements.mouse_pressed && !egui_ctx.wants_keyboard_input() {
+        skelements.history.end_interaction();
+    }
+
     // debug stuff
     if !skelements.made_test {
         armature_window::create_bone(&mut skelements.armature.bones);
@@ -199,23 +401,32 @@ fn draw_ui(egui_ctx: &Context, skelements: &mut Skelements) {
         armature_window::create_bone(&mut skelements.armature.bones);
         skelements.armature.bones[1].parent_id = skelements.armature.bones[0].id;
         skelements.armature.bones[2].parent_id = skelements.armature.bones[1].id;
-        add_image(
-            "/Users/o/downloads/ferris.png".to_string(),
-            &mut skelements.textures,
-        );
+        // fine to go without, it's only there to test with
+        let _ = add_image("/Users/o/downloads/ferris.png".to_string(), skelements);
         skelements.armature.bones[0].pos.x += 0.25;
         skelements.armature.bones[1].pos.x += 0.25;
         skelements.armature.bones[2].pos.x += 0.25;
         skelements.armature.bones[0].tex.idx = usize::MAX;
         skelements.armature.bones[1].tex.idx = 0;
         skelements.armature.bones[2].tex.idx = 0;
-        skelements.selected_bone = 2;
+        selection::select(skelements, 2);
         skelements.made_test = true;
     }
 }
 
 fn draw_mq(stage: &mut Stage) {
     let sk = &mut stage.skelements;
+    let cache = &mut stage.gpu_cache;
+    let batch = &mut stage.batch;
+
+    // repack if textures changed, which clears the atlas
+    if sk.atlas.is_none() {
+        let atlas = pack_textures(&sk.textures, &sk.atlas_options);
+        cache.upload_atlas(&mut stage.mq_ctx, &atlas);
+        sk.atlas = Some(atlas);
+    }
+    let atlas = sk.atlas.as_ref().unwrap();
+    batch.begin_frame();
 
     /*
         many visual effects should not affect the actual
@@ -226,195 +437,93 @@ fn draw_mq(stage: &mut Stage) {
     let mut temp_bones: Vec<Bone> = vec![];
 
     let mut verts: Vec<Vec<Vertex>> = vec![];
+    let mut indices: Vec<Vec<u32>> = vec![];
 
-    let mut i = 0;
-    for b in &mut sk.armature.bones {
-        if sk.textures.len() == 0 {
-            continue;
-        }
-        temp_bones.push(b.clone());
+    // world transforms are kept around for editing,
+    // since bones are moved relative to their parents
+    sk.world_transforms = world_transforms(animation::bones(sk));
 
-        // get parent so it can be inherited
-        let mut p = Bone {
-            scale: Vec2 { x: 1., y: 1. },
-            ..Default::default()
-        };
-        if let Some(pp) = armature_window::find_bone(&temp_bones, b.parent_id) {
-            p = pp.clone();
-        }
-        let tb = &mut temp_bones[i];
-
-        // inherit said parent
-        tb.scale.x *= p.scale.x;
-        tb.scale.y *= p.scale.y;
-        tb.rot += p.rot;
-        b.parent_rot = p.rot;
-
-        // adjust position based on parent's scale
-        tb.pos.x *= p.scale.x;
-        tb.pos.y *= p.scale.y;
-
-        // rotate based on parent
-        tb.pos = rotate(&tb.pos, p.rot);
-
-        // move with parent
-        //
-        // this has to be last, as it's easiest for all
-        // inheritance logic above to process at origin (0, 0)
-        tb.pos.x += p.pos.x;
-        tb.pos.y += p.pos.y;
-
-        // external offsets (camera, window, etc)
-        if tb.parent_id == -1 {
-            tb.pos.x -= sk.camera.pos.x;
-            tb.pos.y -= sk.camera.pos.y;
-
-            // adjust bone scale based on window aspect ratio
-            let ratio = f32::max(sk.window_size.x, sk.window_size.y)
-                / f32::min(sk.window_size.x, sk.window_size.y);
-            if sk.window_size.x > sk.window_size.y {
-                tb.scale.x /= ratio;
-            } else {
-                tb.scale.y /= ratio;
-            }
+    // external offsets (camera, window, etc)
+    let view = view_transform(&sk.camera, &sk.window_size);
 
-            // zoom
-            tb.pos.x *= sk.camera.zoom;
-            tb.pos.y *= sk.camera.zoom;
-            tb.scale.x *= sk.camera.zoom;
-            tb.scale.y *= sk.camera.zoom;
-        }
+    // sprites go in their own order rather than the hierarchy's, so
+    // temp bones are in `order`, which has their index in the armature
+    let order = animation::draw_order(sk);
+    for &idx in &order {
+        let (b, world) = (&animation::bones(sk)[idx], &sk.world_transforms[idx]);
+        let clip = view.compose(world);
+
+        let mut tb = skins_window::skinned(sk, b);
+        tb.pos = clip.translation();
+        tb.rot = world.rotation();
+        tb.scale = clip.scale();
 
         // provide vertices, for use later
-        let mut size = &Vec2::default();
-        if tb.tex.idx != usize::MAX {
-            size = &sk.textures[b.tex.idx].size;
-        }
-        let v = rect_tex_verts(&tb.pos, &tb.scale, &size, tb.rot);
+        let image = clip.compose(&image_transform(&tb));
+        let region = bone_region(atlas, &tb);
+        let skin = mesh::skin(sk, idx, &tb, &view);
+        let (v, i) = sprite_verts(&image, region, bone_texture(&tb), &tb.deform, &skin);
         verts.push(v);
-
-        i += 1;
+        indices.push(i);
+        temp_bones.push(tb);
     }
 
     if temp_bones.len() == 0 {
         return;
     }
 
-    // lowest bones in hierarchy should be targeted first,
-    // which effectively means reversing the list
-    temp_bones.reverse();
-    let len = temp_bones.len();
-    i = len - 1;
-
-    // get bone that's being hovered on
+    // get gizmo handle or bone that's being hovered on. the gizmo
+    // comes first, since it's drawn on top
+    let mouse = screen_to_clip(&sk.mouse, &sk.window_size);
+    let can_hover =
+        sk.mouse_pressed_frames < 5 && !stage.egui_mq.egui_ctx().is_pointer_over_area();
+    if can_hover {
+        sk.gizmo_hover = gizmo::hit(sk);
+    }
     sk.hovered_bone = -1;
-    for tb in &mut temp_bones {
-        if sk.hovered_bone == -1
-            && sk.mouse_pressed_frames < 5
-            && !stage.egui_mq.egui_ctx().is_pointer_over_area()
-            && in_bounding_box(&sk.mouse, &verts[i], &sk.window_size)
-        {
-            sk.hovered_bone = tb.id;
+
+    // bone shapes are drawn over sprites, so they're checked first
+    if can_hover && sk.gizmo_hover == Handle::None {
+        if let Some(idx) = skeleton::hit(sk, &mouse) {
+            sk.hovered_bone = sk.armature.bones[idx].id;
         }
+    }
 
-        if i != 0 {
-            i -= 1;
+    // then sprites from front to back, so the topmost one is picked
+    if sk.hovered_bone == -1 && can_hover && sk.gizmo_hover == Handle::None && sk.show_sprites {
+        for ((tb, v), i) in temp_bones.iter().zip(&verts).zip(&indices).rev() {
+            if hits_sprite(&mouse, v, i, atlas, bone_texture(tb).idx, sk.pixel_hit_test) {
+                sk.hovered_bone = tb.id;
+                break;
+            }
         }
     }
 
-    // go back to normal order for rendering
-    temp_bones.reverse();
+    for (i, tb) in temp_bones.iter().enumerate() {
+        if !sk.show_sprites {
+            break;
+        }
 
-    i = 0;
-    for tb in temp_bones {
         // render appropriate effect if this is the hovered bone
-        // and it's not already selected
-        if sk.hovered_bone == tb.id && sk.selected_bone != i {
-            let b = rect_bind(
-                &mut stage.mq_ctx,
-                &verts[i],
-                &Vec2 {
-                    x: &sk.textures[tb.tex.idx].size.x * tb.scale.x,
-                    y: &sk.textures[tb.tex.idx].size.y * tb.scale.y,
-                },
-                [255, 255, 255, 100],
-            );
-            stage.mq_ctx.apply_bindings(&b);
-            stage.mq_ctx.draw(0, 12, 1);
+        // and it's not already selected, or if it's one of several selected
+        let selected = selection::is_selected(sk, order[i]);
+        if sk.hovered_bone == tb.id && !selected {
+            let image = cache.color(&mut stage.mq_ctx, [255, 255, 255, 100]);
+            batch.shape(&mut stage.mq_ctx, image, &verts[i], &indices[i]);
+        } else if selected && sk.selected_bones.len() > 1 {
+            let image = cache.color(&mut stage.mq_ctx, [120, 170, 255, 80]);
+            batch.shape(&mut stage.mq_ctx, image, &verts[i], &indices[i]);
         }
 
         // the fun part
-        if tb.tex.idx != usize::MAX {
-            let (bindings, _) = rect_tex(
-                &mut stage.mq_ctx,
-                &tb.pos,
-                &tb.scale,
-                &sk.textures[tb.tex.idx],
-                tb.rot,
-            );
-            stage.mq_ctx.apply_bindings(&bindings);
-            stage.mq_ctx.draw(0, 12, 1);
-        }
-
-        // render helper arrows if this is selected
-        if sk.selected_bone == i {
-            // up arrow
-            draw_helper_arrows(
-                &mut stage.mq_ctx,
-                &tb.pos,
-                &Vec2 { x: 0., y: 0.5 },
-                &tb,
-                &sk.window_size,
-                &sk.mouse,
-                0.,
-            );
-
-            // right arrow
-            draw_helper_arrows(
-                &mut stage.mq_ctx,
-                &tb.pos,
-                &Vec2 { x: 0., y: 0.5 },
-                &tb,
-                &sk.window_size,
-                &sk.mouse,
-                (-90. as f32).to_radians(),
-            );
+        if let Some(region) = bone_region(atlas, tb) {
+            let image = cache.page(region.page);
+            batch.shape(&mut stage.mq_ctx, image, &verts[i], &indices[i]);
         }
-
-        i += 1;
     }
-}
 
-fn draw_helper_arrows(
-    mut mq_ctx: &mut Box<dyn RenderingBackend>,
-    pos: &Vec2,
-    offset: &Vec2,
-    bone: &Bone,
-    window_size: &Vec2,
-    mouse: &Vec2,
-    rot: f32,
-) {
-    #[rustfmt::skip]
-    macro_rules! tri_bind {($color:expr) => {
-        tri_bind(
-            &mut mq_ctx,
-            pos.clone(),
-            offset.clone(),
-            Vec2{x: 0.1, y: 0.1},
-            bone.rot + rot,
-            $color,
-        )
-    };}
-
-    let (mut tri, verts) = tri_bind!([100, 100, 100, 0]);
-
-    // recolor triangle if it's hovered
-    if in_bounding_box(&mouse, &verts, &window_size) {
-        (tri, _) = tri_bind!([60, 60, 60, 0]);
-    }
-
-    mq_ctx.apply_bindings(&tri);
-    mq_ctx.draw(0, 12, 1);
+    skeleton::draw(sk, &mut stage.mq_ctx, cache, batch);
+    gizmo::draw(sk, &mut stage.mq_ctx, cache, batch);
 }
 
 fn main() {
@@ -0,0 +1,837 @@
+use std::collections::HashMap;
+
+use skelform_runtime::armature::{
+    bone_texture, image_transform, skin_transforms, vertex_transform,
+};
+use skelform_runtime::atlas::bone_region;
+
+use crate::animation;
+use crate::bindings::mesh_tex_verts;
+use crate::mq_backbone::{
+    Bone, Mesh, MeshBone, Skelements, Texture, Transform2D, Vec2, VertexWeight,
+};
+use crate::skins_window;
+use crate::utils::{barycentric, dist_to_segment, ALPHA_THRESHOLD};
+
+// cells along the longer side of an image when tracing it. the outline
+// follows them, so more gives a tighter fit but more vertices to edit
+const TRACE_CELLS: usize = 24;
+
+// interior vertices go on every this many cells
+const INTERIOR_SPACING: usize = 4;
+
+// cap on edge flips, in case rounding keeps flipping the same ones
+const MAX_FLIPS: usize = 10000;
+
+// most bones a vertex gets weighted to automatically
+const MAX_INFLUENCES: usize = 4;
+
+// automatic weights below this are dropped, so far off bones don't tug at vertices
+const MIN_WEIGHT: f32 = 0.05;
+
+/// build a mesh covering the opaque part of an image: an outline around its
+/// biggest opaque area, with vertices spread inside so it bends smoothly.
+/// images without anything opaque get an empty mesh
+pub fn trace(texture: &Texture) -> Mesh {
+    let (width, height) = (texture.size.x as usize, texture.size.y as usize);
+    if width == 0 || height == 0 {
+        return Mesh::default();
+    }
+    let cell = width.max(height).div_ceil(TRACE_CELLS);
+    let grid = opaque_cells(texture, cell);
+
+    // in pixels from the bottom left, going counter-clockwise
+    let corners = match outline(&grid) {
+        Some(c) => c,
+        None => return Mesh::default(),
+    };
+    let corners: Vec<Vec2> = corners
+        .iter()
+        .map(|(x, y)| Vec2 {
+            x: ((x * cell) as f32).min(width as f32),
+            y: ((y * cell) as f32).min(height as f32),
+        })
+        .collect();
+
+    // cells were grown by one, so cutting corners by less than that
+    // doesn't leave any opaque pixels out
+    let simplified = simplify(&corners, cell as f32);
+    let mut points = if simplified.len() >= 3 && is_simple(&simplified) {
+        simplified
+    } else {
+        corners
+    };
+    let outline_len = points.len();
+
+    // interior vertices, kept off the outline so triangles don't get too thin
+    for gy in (INTERIOR_SPACING..grid.height).step_by(INTERIOR_SPACING) {
+        for gx in (INTERIOR_SPACING..grid.width).step_by(INTERIOR_SPACING) {
+            let p = Vec2 {
+                x: (gx * cell) as f32,
+                y: (gy * cell) as f32,
+            };
+            let clear = (0..outline_len).all(|i| {
+                let (a, b) = (&points[i], &points[(i + 1) % outline_len]);
+                dist_to_segment(&p, a, b) >= cell as f32 * 1.5
+            });
+            if clear && in_polygon(&p, &points[..outline_len]) {
+                points.push(p);
+            }
+        }
+    }
+
+    let mut triangles = ear_clip(&points[..outline_len]);
+    for i in outline_len..points.len() {
+        insert_point(&points, &mut triangles, i);
+    }
+    flip_to_delaunay(&points, &mut triangles);
+
+    Mesh {
+        vertices: points
+            .iter()
+            .map(|p| Vec2 {
+                x: p.x / width as f32,
+                y: p.y / height as f32,
+            })
+            .collect(),
+        outline: outline_len,
+        triangles: triangles.iter().flatten().map(|i| *i as u32).collect(),
+        ..Default::default()
+    }
+}
+
+/// world positions of the vertices of the mesh a bone shows (with the current
+/// skin), moved by its deform and bent by its weights. `None` if it isn't
+/// showing a mesh
+pub fn world_vertices(sk: &Skelements, idx: usize) -> Option<Vec<Vec2>> {
+    let bone = skins_window::skinned(sk, animation::bones(sk).get(idx)?);
+    let skin = skin(sk, idx, &bone, &Transform2D::IDENTITY);
+    placed_vertices(sk, idx, &bone, &skin)
+}
+
+fn placed_vertices(
+    sk: &Skelements,
+    idx: usize,
+    bone: &Bone,
+    skin: &[Transform2D],
+) -> Option<Vec<Vec2>> {
+    let tex = bone_texture(bone);
+    if tex.mesh.triangles.is_empty() {
+        return None;
+    }
+    let region = bone_region(sk.atlas.as_ref()?, bone)?;
+    let image = sk
+        .world_transforms
+        .get(idx)?
+        .compose(&image_transform(bone));
+    let verts = mesh_tex_verts(&image, region, tex, &bone.deform, skin);
+    Some(verts.into_iter().map(|v| v.pos).collect())
+}
+
+/// transforms from the image space of the mesh a bone (at `idx`) shows to
+/// the space `outer` maps world space to, for each bone the mesh is bound to
+/// (see `skin_transforms`)
+pub fn skin(sk: &Skelements, idx: usize, bone: &Bone, outer: &Transform2D) -> Vec<Transform2D> {
+    let image = image_transform(bone);
+    let mesh = &bone_texture(bone).mesh;
+    skin_transforms(mesh, &sk.armature.bones, &sk.world_transforms, idx)
+        .iter()
+        .map(|t| outer.compose(t).compose(&image))
+        .collect()
+}
+
+/// transform from the image space of the mesh a bone shows to world space
+/// at one of its vertices, since weights bend each one differently
+pub fn vertex_to_world(
+    sk: &Skelements,
+    idx: usize,
+    bone: &Bone,
+    vertex: usize,
+) -> Option<Transform2D> {
+    let image = sk
+        .world_transforms
+        .get(idx)?
+        .compose(&image_transform(bone));
+    let skin = skin(sk, idx, bone, &Transform2D::IDENTITY);
+    Some(vertex_transform(
+        &bone_texture(bone).mesh,
+        vertex,
+        &image,
+        &skin,
+    ))
+}
+
+/// let a mesh's vertices be weighted to the bone at `bone`, binding it where
+/// it is now relative to the bone at `idx`, which shows the mesh. that one
+/// is bound as well, as weight taken off the others goes back to it
+pub fn bind_bone(sk: &Skelements, idx: usize, mesh: &mut Mesh, bone: usize) {
+    let from = match sk.world_transforms.get(idx) {
+        Some(w) => w,
+        None => return,
+    };
+    for i in [idx, bone] {
+        let (id, to) = match (sk.armature.bones.get(i), sk.world_transforms.get(i)) {
+            (Some(b), Some(w)) => (b.id, w),
+            _ => continue,
+        };
+        if mesh.bones.iter().any(|b| b.bone_id == id) {
+            continue;
+        }
+        mesh.bones.push(MeshBone {
+            bone_id: id,
+            bind: to.invert().unwrap_or(Transform2D::IDENTITY).compose(from),
+        });
+    }
+}
+
+/// stop a mesh's vertices from following a bone, such as one being deleted
+pub fn unbind(mesh: &mut Mesh, bone_id: i32) {
+    mesh.bones.retain(|b| b.bone_id != bone_id);
+    for weights in &mut mesh.weights {
+        weights.retain(|w| w.bone_id != bone_id);
+        normalize(weights);
+    }
+}
+
+/// the mesh a bone (at `idx`) shows, with each vertex weighted to the
+/// closest of `candidates` (also bone indices) and bound to them as they
+/// are now. `None` if it isn't showing a mesh
+pub fn auto_weights(sk: &Skelements, idx: usize, candidates: &[usize]) -> Option<Mesh> {
+    let bone = skins_window::skinned(sk, sk.armature.bones.get(idx)?);
+
+    // where vertices are without weights, which is where binding leaves them
+    let vertices = placed_vertices(sk, idx, &bone, &[])?;
+
+    let mut mesh = bone_texture(&bone).mesh.clone();
+    mesh.bones.clear();
+    for &c in candidates {
+        bind_bone(sk, idx, &mut mesh, c);
+    }
+
+    // each candidate as a segment from its origin to its tip
+    let segments: Vec<(i32, Vec2, Vec2)> = candidates
+        .iter()
+        .filter_map(|&c| {
+            let (b, world) = (sk.armature.bones.get(c)?, sk.world_transforms.get(c)?);
+            let tip = world.transform_point(&Vec2 { x: b.length, y: 0. });
+            Some((b.id, world.translation(), tip))
+        })
+        .collect();
+
+    mesh.weights = vertices
+        .iter()
+        .map(|v| {
+            // by inverse square distance, so the closest bone wins out
+            let mut near: Vec<VertexWeight> = segments
+                .iter()
+                .map(|(id, a, b)| {
+                    let d = dist_to_segment(v, a, b);
+                    VertexWeight {
+                        bone_id: *id,
+                        weight: 1. / (d * d).max(f32::EPSILON),
+                    }
+                })
+                .collect();
+            near.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+            near.truncate(MAX_INFLUENCES);
+            normalize(&mut near);
+            near.retain(|w| w.weight >= MIN_WEIGHT);
+            normalize(&mut near);
+            near
+        })
+        .collect();
+    Some(mesh)
+}
+
+/// how much a vertex follows a bone, out of 1. vertices
+/// without weights only follow `owner_id`, which shows the mesh
+pub fn weight(mesh: &Mesh, vertex: usize, bone_id: i32, owner_id: i32) -> f32 {
+    let weights = mesh.weights.get(vertex).map(|w| &w[..]).unwrap_or_default();
+    let total: f32 = weights.iter().map(|w| w.weight).sum();
+    if total <= 0. {
+        return if bone_id == owner_id { 1. } else { 0. };
+    }
+    let own: f32 = weights
+        .iter()
+        .filter(|w| w.bone_id == bone_id)
+        .map(|w| w.weight)
+        .sum();
+    own / total
+}
+
+/// set how much a vertex follows a bone, scaling its other weights so they
+/// still add up to 1. if it has no others, the rest goes to `owner_id`
+pub fn set_weight(mesh: &mut Mesh, vertex: usize, bone_id: i32, weight: f32, owner_id: i32) {
+    if mesh.weights.len() < mesh.vertices.len() {
+        mesh.weights.resize(mesh.vertices.len(), vec![]);
+    }
+    let weights = match mesh.weights.get_mut(vertex) {
+        Some(w) => w,
+        None => return,
+    };
+    let weight = weight.clamp(0., 1.);
+
+    weights.retain(|w| w.bone_id != bone_id);
+    let others: f32 = weights.iter().map(|w| w.weight).sum();
+    if others > 0. {
+        for w in weights.iter_mut() {
+            w.weight *= (1. - weight) / others;
+        }
+    } else if bone_id != owner_id {
+        weights.push(VertexWeight {
+            bone_id: owner_id,
+            weight: 1. - weight,
+        });
+    }
+    weights.push(VertexWeight { bone_id, weight });
+    weights.retain(|w| w.weight > 0.);
+}
+
+/// scale weights so they add up to 1, unless they're all 0
+fn normalize(weights: &mut [VertexWeight]) {
+    let total: f32 = weights.iter().map(|w| w.weight).sum();
+    if total <= 0. {
+        return;
+    }
+    for w in weights {
+        w.weight /= total;
+    }
+}
+
+/// every edge of a mesh's triangles once, as pairs of vertex indices
+pub fn edges(mesh: &Mesh) -> Vec<(usize, usize)> {
+    let mut edges = vec![];
+    for tri in mesh.triangles.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (tri[k] as usize, tri[(k + 1) % 3] as usize);
+            let edge = (a.min(b), a.max(b));
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+    }
+    edges
+}
+
+/// cells of an image, in rows from the bottom
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    fn get(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        self.cells[y as usize * self.width + x as usize]
+    }
+
+    fn set(&mut self, x: i32, y: i32) {
+        self.cells[y as usize * self.width + x as usize] = true;
+    }
+}
+
+/// cells of `cell` pixels that are near anything opaque, narrowed down
+/// to the biggest area of them that's connected
+fn opaque_cells(texture: &Texture, cell: usize) -> Grid {
+    let (width, height) = (texture.size.x as usize, texture.size.y as usize);
+    let mut opaque = Grid {
+        width: width.div_ceil(cell),
+        height: height.div_ceil(cell),
+        cells: vec![],
+    };
+    opaque.cells = vec![false; opaque.width * opaque.height];
+    for y in 0..height {
+        for x in 0..width {
+            if texture.bytes[(y * width + x) * 4 + 3] >= ALPHA_THRESHOLD {
+                opaque.set((x / cell) as i32, (y / cell) as i32);
+            }
+        }
+    }
+
+    // grow by a cell in every direction, to leave room for simplifying
+    let mut grid = Grid {
+        width: opaque.width,
+        height: opaque.height,
+        cells: opaque.cells.clone(),
+    };
+    for y in 0..grid.height as i32 {
+        for x in 0..grid.width as i32 {
+            let near = (-1..=1).any(|dy| (-1..=1).any(|dx| opaque.get(x + dx, y + dy)));
+            if near {
+                grid.set(x, y);
+            }
+        }
+    }
+
+    // cells only touching at a corner would make the outline cross itself
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for y in 0..grid.height as i32 - 1 {
+            for x in 0..grid.width as i32 - 1 {
+                let [a, b, c, d] =
+                    [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| grid.get(x + dx, y + dy));
+                if a && d && !b && !c {
+                    grid.set(x + 1, y);
+                    changed = true;
+                } else if b && c && !a && !d {
+                    grid.set(x, y);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // keep only the biggest area
+    let mut area = vec![usize::MAX; grid.cells.len()];
+    let mut sizes = vec![];
+    for start in 0..grid.cells.len() {
+        if !grid.cells[start] || area[start] != usize::MAX {
+            continue;
+        }
+        let id = sizes.len();
+        let mut size = 0;
+        let mut stack = vec![start];
+        area[start] = id;
+        while let Some(i) = stack.pop() {
+            size += 1;
+            let (x, y) = ((i % grid.width) as i32, (i / grid.width) as i32);
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let (nx, ny) = (x + dx, y + dy);
+                if !grid.get(nx, ny) {
+                    continue;
+                }
+                let n = ny as usize * grid.width + nx as usize;
+                if area[n] == usize::MAX {
+                    area[n] = id;
+                    stack.push(n);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    let biggest = (0..sizes.len()).max_by_key(|i| sizes[*i]);
+    for (i, c) in grid.cells.iter_mut().enumerate() {
+        *c = *c && Some(area[i]) == biggest;
+    }
+    grid
+}
+
+/// corners around the outside of the grid's filled cells, in cells,
+/// going counter-clockwise. holes are left out
+fn outline(grid: &Grid) -> Option<Vec<(usize, usize)>> {
+    // edges of each filled cell that face an empty one, directed so the
+    // cell is on their left. without cells touching only at a corner,
+    // each corner has at most one of them leaving it
+    let mut next: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut start = None;
+    for y in 0..grid.height as i32 {
+        for x in 0..grid.width as i32 {
+            if !grid.get(x, y) {
+                continue;
+            }
+            if !grid.get(x, y - 1) {
+                next.insert((x, y), (x + 1, y));
+                // the lowest cell's bottom is always on the outside
+                start.get_or_insert((x, y));
+            }
+            if !grid.get(x + 1, y) {
+                next.insert((x + 1, y), (x + 1, y + 1));
+            }
+            if !grid.get(x, y + 1) {
+                next.insert((x + 1, y + 1), (x, y + 1));
+            }
+            if !grid.get(x - 1, y) {
+                next.insert((x, y + 1), (x, y));
+            }
+        }
+    }
+
+    let start = start?;
+    let mut loop_corners = vec![start];
+    let mut corner = *next.get(&start)?;
+    while corner != start && loop_corners.len() <= next.len() {
+        loop_corners.push(corner);
+        corner = *next.get(&corner)?;
+    }
+
+    // only keep corners where the outline turns
+    let n = loop_corners.len();
+    let corners = (0..n)
+        .filter(|i| {
+            let (px, py) = loop_corners[(i + n - 1) % n];
+            let (x, y) = loop_corners[*i];
+            let (nx, ny) = loop_corners[(i + 1) % n];
+            (x - px, y - py) != (nx - x, ny - y)
+        })
+        .map(|i| (loop_corners[i].0 as usize, loop_corners[i].1 as usize))
+        .collect();
+    Some(corners)
+}
+
+/// drop points of a closed outline that are within `epsilon` of the line
+/// between the ones around them (Douglas-Peucker)
+fn simplify(points: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    let n = points.len();
+    if n < 4 {
+        return points.to_vec();
+    }
+
+    // split the loop in two at the point farthest from the first
+    let far = (1..n)
+        .max_by(|a, b| {
+            let da = points[*a].sub(&points[0]).length();
+            let db = points[*b].sub(&points[0]).length();
+            da.total_cmp(&db)
+        })
+        .unwrap_or(1);
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[far] = true;
+    keep_farthest(points, 0, far, epsilon, &mut keep);
+    keep_farthest(points, far, n, epsilon, &mut keep);
+
+    (0..n)
+        .filter(|i| keep[*i])
+        .map(|i| points[i].clone())
+        .collect()
+}
+
+/// keep the point between `start` and `end` farthest from the line between
+/// them if it's past `epsilon`, then do the same on either side of it.
+/// indices wrap around, so `end` can be the length to mean the first point
+fn keep_farthest(points: &[Vec2], start: usize, end: usize, epsilon: f32, keep: &mut [bool]) {
+    let n = points.len();
+    let (a, b) = (&points[start % n], &points[end % n]);
+    let mut farthest = None;
+    let mut max_dist = epsilon;
+    for i in start + 1..end {
+        let d = dist_to_segment(&points[i % n], a, b);
+        if d > max_dist {
+            max_dist = d;
+            farthest = Some(i);
+        }
+    }
+    if let Some(i) = farthest {
+        keep[i % n] = true;
+        keep_farthest(points, start, i, epsilon, keep);
+        keep_farthest(points, i, end, epsilon, keep);
+    }
+}
+
+/// whether no two edges of a closed outline cross
+fn is_simple(points: &[Vec2]) -> bool {
+    let n = points.len();
+    for i in 0..n {
+        for j in i + 2..n {
+            // neighbors share a point, so they always touch
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (a, b) = (&points[i], &points[(i + 1) % n]);
+            let (c, d) = (&points[j], &points[(j + 1) % n]);
+            if segments_cross(a, b, c, d) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// split a counter-clockwise outline into triangles, by cutting off
+/// corners (ears) that have nothing else inside them
+fn ear_clip(points: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            )
+        };
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+            let (pa, pb, pc) = (&points[a], &points[b], &points[c]);
+            cross(pa, pb, pc) > 0.
+                && remaining
+                    .iter()
+                    .filter(|r| ![a, b, c].contains(*r))
+                    .all(|r| barycentric(&points[*r], pa, pb, pc).is_none())
+        };
+
+        // an outline that isn't quite simple may have no ears left,
+        // so cut one off anyway rather than getting stuck
+        let ear = (0..n).find(|i| is_ear(*i)).unwrap_or(0);
+        let (a, b, c) = corner(ear);
+        triangles.push([a, b, c]);
+        remaining.remove(ear);
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+/// add a point inside the triangles by splitting up whichever one it's in.
+/// points right on an edge split both triangles sharing it instead
+fn insert_point(points: &[Vec2], triangles: &mut Vec<[usize; 3]>, p: usize) {
+    for t in 0..triangles.len() {
+        let [a, b, c] = triangles[t];
+        let w = match barycentric(&points[p], &points[a], &points[b], &points[c]) {
+            Some(w) => w,
+            None => continue,
+        };
+
+        // index of the corner across from the edge it's on, if any
+        let on_edge = (0..3).find(|k| w[*k] < 1e-5);
+        let k = match on_edge {
+            Some(k) => k,
+            None => {
+                triangles[t] = [a, b, p];
+                triangles.push([b, c, p]);
+                triangles.push([c, a, p]);
+                return;
+            }
+        };
+
+        let tri = triangles[t];
+        let (u, v, opposite) = (tri[(k + 1) % 3], tri[(k + 2) % 3], tri[k]);
+        let other = match triangles.iter().position(|o| has_edge(o, v, u)) {
+            Some(o) => o,
+            None => return, // on the outline, which shouldn't happen
+        };
+        let across = *triangles[other]
+            .iter()
+            .find(|i| **i != u && **i != v)
+            .unwrap_or(&u);
+        triangles[t] = [u, p, opposite];
+        triangles.push([p, v, opposite]);
+        triangles[other] = [v, p, across];
+        triangles.push([p, u, across]);
+        return;
+    }
+}
+
+/// flip edges between triangles until none has a point of its neighbor
+/// inside its circumcircle, which avoids long, thin triangles
+fn flip_to_delaunay(points: &[Vec2], triangles: &mut [[usize; 3]]) {
+    for _ in 0..MAX_FLIPS {
+        let mut owner: HashMap<(usize, usize), usize> = HashMap::new();
+        for (t, tri) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                owner.insert((tri[k], tri[(k + 1) % 3]), t);
+            }
+        }
+
+        let mut flip = None;
+        'search: for (t, tri) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                let (a, b, c) = (tri[k], tri[(k + 1) % 3], tri[(k + 2) % 3]);
+                let other = match owner.get(&(b, a)) {
+                    Some(o) => *o,
+                    None => continue,
+                };
+                let d = match triangles[other].iter().find(|i| **i != a && **i != b) {
+                    Some(d) => *d,
+                    None => continue,
+                };
+                let (pa, pb, pc, pd) = (&points[a], &points[b], &points[c], &points[d]);
+                if in_circumcircle(pa, pb, pc, pd)
+                    && cross(pa, pd, pc) > 0.
+                    && cross(pd, pb, pc) > 0.
+                {
+                    flip = Some((t, other, [a, d, c], [d, b, c]));
+                    break 'search;
+                }
+            }
+        }
+
+        match flip {
+            Some((t, other, first, second)) => {
+                triangles[t] = first;
+                triangles[other] = second;
+            }
+            None => return,
+        }
+    }
+}
+
+fn has_edge(tri: &[usize; 3], a: usize, b: usize) -> bool {
+    (0..3).any(|k| tri[k] == a && tri[(k + 1) % 3] == b)
+}
+
+/// whether `d` is inside the circle through the counter-clockwise `a`, `b`, `c`
+fn in_circumcircle(a: &Vec2, b: &Vec2, c: &Vec2, d: &Vec2) -> bool {
+    let [ad, bd, cd] = [a, b, c].map(|p| p.sub(d));
+    let sq = |v: &Vec2| v.x * v.x + v.y * v.y;
+    let det = ad.x * (bd.y * sq(&cd) - sq(&bd) * cd.y) - ad.y * (bd.x * sq(&cd) - sq(&bd) * cd.x)
+        + sq(&ad) * (bd.x * cd.y - bd.y * cd.x);
+    det > 1e-6
+}
+
+fn in_polygon(p: &Vec2, polygon: &[Vec2]) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (&polygon[i], &polygon[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn segments_cross(a: &Vec2, b: &Vec2, c: &Vec2, d: &Vec2) -> bool {
+    let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+    let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+// vector helpers
+
+/// positive if `a`, `b`, `c` go counter-clockwise
+fn cross(a: &Vec2, b: &Vec2, c: &Vec2) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    fn polygon_area(polygon: &[Vec2]) -> f32 {
+        let n = polygon.len();
+        (0..n)
+            .map(|i| {
+                let (a, b) = (&polygon[i], &polygon[(i + 1) % n]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f32>()
+            / 2.
+    }
+
+    /// triangles don't overlap and cover the outline if they all face the
+    /// same way, sit inside it, and add up to its area
+    fn assert_covers(points: &[Vec2], outline: usize, triangles: &[[usize; 3]]) {
+        let mut area = 0.;
+        for [a, b, c] in triangles {
+            let (pa, pb, pc) = (&points[*a], &points[*b], &points[*c]);
+            assert!(cross(pa, pb, pc) > 0., "triangle isn't counter-clockwise");
+            let center = pa.add(pb).add(pc).scale(1. / 3.);
+            assert!(in_polygon(&center, &points[..outline]));
+            area += cross(pa, pb, pc) / 2.;
+        }
+        let expected = polygon_area(&points[..outline]);
+        assert!((area - expected).abs() < expected * 1e-4);
+    }
+
+    #[test]
+    fn ear_clip_covers_concave_outline() {
+        // an L shape, going counter-clockwise
+        let points = vec![
+            v(0., 0.),
+            v(4., 0.),
+            v(4., 1.),
+            v(1., 1.),
+            v(1., 4.),
+            v(0., 4.),
+        ];
+        let triangles = ear_clip(&points);
+        assert_eq!(triangles.len(), points.len() - 2);
+        assert_covers(&points, points.len(), &triangles);
+    }
+
+    #[test]
+    fn inserted_points_keep_covering() {
+        let mut points = vec![v(0., 0.), v(4., 0.), v(4., 4.), v(0., 4.)];
+        let mut triangles = ear_clip(&points);
+
+        // one inside a triangle, then one on the edge between two
+        points.push(v(3., 1.));
+        insert_point(&points, &mut triangles, 4);
+        assert_eq!(triangles.len(), 4);
+        points.push(v(3., 3.));
+        insert_point(&points, &mut triangles, 5);
+
+        assert!(triangles.iter().any(|t| t.contains(&5)));
+        assert_covers(&points, 4, &triangles);
+    }
+
+    #[test]
+    fn flip_to_delaunay_flips_long_diagonal() {
+        // a wide diamond split along its long diagonal, which leaves the
+        // top and bottom corners inside each other's circumcircles
+        let points = vec![v(-3., 0.), v(0., -1.), v(3., 0.), v(0., 1.)];
+        let mut triangles = [[0, 1, 2], [0, 2, 3]];
+        flip_to_delaunay(&points, &mut triangles);
+
+        assert!(!triangles
+            .iter()
+            .any(|t| has_edge(t, 0, 2) || has_edge(t, 2, 0)));
+        assert!(triangles
+            .iter()
+            .any(|t| has_edge(t, 1, 3) || has_edge(t, 3, 1)));
+        for [a, b, c] in &triangles {
+            let (pa, pb, pc) = (&points[*a], &points[*b], &points[*c]);
+            assert!(cross(pa, pb, pc) > 0.);
+            for d in (0..points.len()).filter(|d| ![*a, *b, *c].contains(d)) {
+                assert!(!in_circumcircle(pa, pb, pc, &points[d]));
+            }
+        }
+        assert_covers(&points, points.len(), &triangles);
+    }
+
+    #[test]
+    fn trace_covers_opaque_rectangle() {
+        let (width, height) = (40, 32);
+        let opaque = |x: usize, y: usize| (8..32).contains(&x) && (6..26).contains(&y);
+        let mut bytes = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let alpha = if opaque(x, y) { 255 } else { 0 };
+                bytes.extend_from_slice(&[255, 255, 255, alpha]);
+            }
+        }
+        let texture = Texture {
+            size: v(width as f32, height as f32),
+            bytes,
+            ..Default::default()
+        };
+
+        let mesh = trace(&texture);
+        assert!(mesh.outline >= 3);
+        assert!(mesh.vertices.len() > mesh.outline, "no interior vertices");
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|p| (0. ..=1.).contains(&p.x) && (0. ..=1.).contains(&p.y)));
+
+        let triangles: Vec<[usize; 3]> = mesh
+            .triangles
+            .chunks(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+            .collect();
+        assert_covers(&mesh.vertices, mesh.outline, &triangles);
+
+        // every opaque pixel is inside the outline
+        let outline = &mesh.vertices[..mesh.outline];
+        for y in 0..height {
+            for x in (0..width).filter(|x| opaque(*x, y)) {
+                let center = v(
+                    (x as f32 + 0.5) / width as f32,
+                    (y as f32 + 0.5) / height as f32,
+                );
+                assert!(in_polygon(&center, outline));
+            }
+        }
+    }
+}
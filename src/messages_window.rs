@@ -0,0 +1,28 @@
+use egui::{Align2, Context, Vec2};
+
+use crate::mq_backbone::Skelements;
+
+/// problems that didn't stop what was being done, like images
+/// missing from an opened project. only shown while there are any
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    if skelements.messages.is_empty() {
+        return;
+    }
+
+    let mut dismissed = false;
+    egui::Window::new("Messages")
+        .movable(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_TOP, Vec2 { x: 0., y: 35. })
+        .show(egui_ctx, |ui| {
+            for m in &skelements.messages {
+                ui.label(m);
+            }
+            if ui.button("Dismiss").clicked() {
+                dismissed = true;
+            }
+        });
+    if dismissed {
+        skelements.messages.clear();
+    }
+}
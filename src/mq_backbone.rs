@@ -1,50 +1,53 @@
-use image::{ImageBuffer, ImageReader, Rgba};
+use std::{fs, path::Path};
+
+use image::{ImageBuffer, Rgba};
 use mq::*;
 use {egui_miniquad as egui_mq, miniquad as mq};
 
+use crate::batch::{RenderStats, SpriteBatch};
 use crate::bindings::*;
+use crate::gizmo::{Drag, Handle};
+use crate::keymap::{self, Keymap};
+use crate::undo::History;
+
+use skelform_runtime::atlas::{self, AtlasImage};
+pub use skelform_runtime::project::Camera;
+pub use skelform_runtime::{
+    Animation, Armature, Atlas, AtlasOptions, AtlasRegion, Attachment, Bone, BoneTexture,
+    BoneTrack, Curve, Interpolation, Keyframe, Mesh, MeshBone, Skin, SkinAttachment, Transform2D,
+    Vec2, VertexWeight,
+};
 
 #[repr(C)]
-#[derive(Clone, Default)]
-pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
-}
-
-#[repr(C)]
+#[derive(Clone)]
 pub struct Vertex {
     pub pos: Vec2,
     pub uv: Vec2,
 }
 
-#[derive(Clone, Default)]
-pub struct Armature {
-    pub bones: Vec<Bone>,
+#[derive(Clone, Copy, PartialEq)]
+pub enum AnimProperty {
+    Pos,
+    Rot,
+    Scale,
+    Attachment,
+    Deform,
 }
 
-#[derive(Clone, Default)]
-pub struct BoneTexture {
-    pub idx: usize, // index relative to skelements texture vector
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum PlayMode {
+    Once,
+    #[default]
+    Loop,
+    PingPong,
 }
 
-#[derive(Clone, Default)]
-pub struct Bone {
-    pub name: String,
-    pub parent_id: i32,
-    pub pos: Vec2,
-    pub rot: f32,
-    pub scale: Vec2,
-    pub id: i32,
-    pub tex: BoneTexture,
-
-    // used to properly offset bone's movement to counteract it's parent
-    pub parent_rot: f32,
-}
-
-#[derive(Default)]
-pub struct Camera {
-    pub pos: Vec2,
-    pub zoom: f32,
+/// identifies a single keyframe in the selected animation
+#[derive(Clone, Copy, PartialEq)]
+pub struct SelectedKey {
+    pub bone_id: i32,
+    pub prop: AnimProperty,
+    pub frame: i32,
 }
 
 /// persistent data that lives in stage, but benefits
@@ -52,8 +55,11 @@ pub struct Camera {
 #[derive(Default)]
 pub struct Skelements {
     pub armature: Armature,
-    pub selected_bone: usize,
-    pub textures: Vec<Texture>,
+    pub selected_bone: usize, // the active one, out of `selected_bones`
+    pub selected_bones: Vec<usize>,
+    pub textures: Vec<Texture>, // set `atlas` to None whenever these change
+    pub atlas: Option<Atlas>,   // textures packed together, None to repack
+    pub atlas_options: AtlasOptions,
 
     // u-related stuff
     pub dragging: bool,
@@ -64,7 +70,42 @@ pub struct Skelements {
     pub mouse_prev: Vec2, // used to get mouse velocity
     pub window_size: Vec2,
     pub hovered_bone: i32,
+    pub marquee_start: Option<Vec2>, // where a rubber-band selection started
+    pub panning: bool,
+    pub space_held: bool, // left-dragging pans while it's held
+    pub gizmo_hover: Handle,
+    pub drag: Option<Drag>, // of the selection, with the gizmo or otherwise
+    pub pixel_hit_test: bool, // ignore transparent pixels when hovering
     pub camera: Camera,
+    pub world_transforms: Vec<Transform2D>, // of each bone, as of last render
+    pub render_stats: RenderStats,          // of the last frame
+    pub show_stats: bool,
+    pub show_sprites: bool,
+    pub show_bones: bool,      // as shapes, so they're visible without textures
+    pub show_bone_lines: bool, // from each parent to its children
+    pub skin: String,          // shown on the canvas, or empty for bones' own attachments
+    pub messages: Vec<String>, // problems to tell the user about, until dismissed
+    pub weight_bone: i32,      // id of the bone whose weights are painted, or -1
+    pub brush_strength: f32,   // weight painted at the middle of the brush
+
+    // animation-related stuff
+    pub selected_anim: usize,
+    pub anim_frame: i32,
+    pub selected_key: Option<SelectedKey>,
+    pub playing: bool,
+    pub play_mode: PlayMode,
+    pub play_elapsed: f32, // fractional frame, only used while playing
+    pub play_forward: bool,
+    pub posed_frame: f32, // frame the armature was last posed at (-1 to force)
+
+    // the armature's bones and draw order as posed by the selected animation,
+    // which are shown and edited instead, so the setup pose stays as it is
+    pub posed_bones: Vec<Bone>,
+    pub posed_draw_order: Vec<i32>,
+    pub last_update: f64,
+
+    pub history: History,
+    pub keymap: Keymap,
 
     // debugging
     pub made_test: bool,
@@ -75,6 +116,8 @@ pub struct Stage {
     pub mq_ctx: Box<dyn mq::RenderingBackend>,
     pub pipeline: Pipeline,
     pub bindings: Bindings,
+    pub gpu_cache: GpuCache,
+    pub batch: SpriteBatch,
     pub skelements: Skelements,
 }
 
@@ -82,6 +125,10 @@ pub struct Stage {
 pub struct Texture {
     pub size: Vec2,
     pub bytes: Vec<u8>,
+
+    // original image file, kept around so it can be bundled with the project
+    pub name: String,
+    pub file: Vec<u8>,
 }
 
 impl Stage {
@@ -127,15 +174,30 @@ impl Stage {
         );
 
         let bindings = placeholder_binding(&mut mq_ctx);
+        let batch = SpriteBatch::new(&mut mq_ctx);
+        let (keymap, messages) = keymap::load();
 
         Self {
             egui_mq: egui_mq::EguiMq::new(&mut *mq_ctx),
             mq_ctx,
             pipeline,
             bindings,
+            gpu_cache: GpuCache::default(),
+            batch,
             skelements: Skelements {
                 selected_bone: usize::MAX,
+                selected_anim: usize::MAX,
+                posed_frame: -1.,
+                last_update: mq::date::now(),
                 textures: textures,
+                keymap,
+                messages,
+                pixel_hit_test: true,
+                show_sprites: true,
+                show_bones: true,
+                show_bone_lines: true,
+                weight_bone: -1,
+                brush_strength: 1.,
                 camera: Camera{
                     zoom: 1.,
                     ..Default::default()
@@ -226,11 +288,35 @@ pub mod shader {
     }
 }
 
-pub fn add_image(path: String, textures: &mut Vec<Texture>) {
-    let img = ImageReader::open(path)
-        .unwrap()
-        .decode()
-        .unwrap()
+/// load an image file as a new texture, and have the atlas repacked with it
+pub fn add_image(path: String, skelements: &mut Skelements) -> Result<(), String> {
+    let file = fs::read(&path).map_err(|e| e.to_string())?;
+    let name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    skelements.textures.push(load_texture(name, file)?);
+    skelements.atlas = None;
+    Ok(())
+}
+
+/// pack all textures into an atlas, with regions in the same order
+pub fn pack_textures(textures: &[Texture], options: &AtlasOptions) -> Atlas {
+    let images: Vec<AtlasImage> = textures
+        .iter()
+        .map(|t| AtlasImage {
+            width: t.size.x as u32,
+            height: t.size.y as u32,
+            rgba: &t.bytes,
+        })
+        .collect();
+    atlas::pack(&images, options)
+}
+
+/// decode an image file into a texture
+pub fn load_texture(name: String, file: Vec<u8>) -> Result<Texture, String> {
+    let img = image::load_from_memory(&file)
+        .map_err(|e| e.to_string())?
         .flipv()
         .to_rgba8();
 
@@ -243,11 +329,13 @@ pub fn add_image(path: String, textures: &mut Vec<Texture>) {
             img_buf.put_pixel(x, y, *img.get_pixel(x, y));
         }
     }
-    textures.push(Texture {
+    Ok(Texture {
         size: Vec2{
             x: img_buf.width() as f32,
             y: img_buf.height() as f32
         },
         bytes: img_buf.to_vec(),
+        name,
+        file,
     })
 }
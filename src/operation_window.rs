@@ -11,7 +11,10 @@ pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
             let buttons = [
                 "Translate", 
                 "Rotate", 
-                "Scale"
+                "Scale",
+                "Pivot",
+                "Mesh",
+                "Weights"
             ];
 
                 let mut i = 0;
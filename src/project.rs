@@ -0,0 +1,97 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, RgbaImage};
+use skelform_runtime::project::{self, ExportFile, Image, ProjectFile};
+
+use crate::mq_backbone::{load_texture, pack_textures, Skelements};
+use crate::selection;
+
+pub use skelform_runtime::project::{EXPORT_EXTENSION, EXTENSION};
+
+pub fn save(path: &str, skelements: &Skelements) -> Result<(), String> {
+    let images = skelements
+        .textures
+        .iter()
+        .map(|t| Image {
+            name: t.name.clone(),
+            file: t.file.clone(),
+        })
+        .collect();
+
+    project::write(
+        path,
+        &ProjectFile {
+            armature: skelements.armature.clone(),
+            camera: skelements.camera.clone(),
+            atlas: skelements.atlas_options.clone(),
+            images,
+            warnings: vec![],
+        },
+    )
+}
+
+pub fn open(path: &str, skelements: &mut Skelements) -> Result<(), String> {
+    let mut file = project::read(path)?;
+
+    // decode images, dropping any that can't be
+    let mut textures = vec![];
+    let mut new_idx = vec![usize::MAX; file.images.len()];
+    for (i, img) in file.images.into_iter().enumerate() {
+        let name = img.name.clone();
+        match load_texture(img.name, img.file) {
+            Ok(tex) => {
+                new_idx[i] = textures.len();
+                textures.push(tex);
+            }
+            Err(e) => file
+                .warnings
+                .push(format!("Could not load texture '{}': {}", name, e)),
+        }
+    }
+    project::repoint_textures(&mut file.armature.bones, &new_idx);
+
+    skelements.armature = file.armature;
+    skelements.textures = textures;
+    skelements.atlas = None;
+    skelements.atlas_options = file.atlas;
+    skelements.camera = file.camera;
+    selection::clear(skelements);
+    skelements.hovered_bone = -1;
+    skelements.selected_anim = usize::MAX;
+    skelements.anim_frame = 0;
+    skelements.selected_key = None;
+    skelements.playing = false;
+    skelements.messages = file.warnings;
+
+    // edits from the previous project don't apply to this one
+    skelements.history = Default::default();
+
+    Ok(())
+}
+
+/// write the armature with its textures packed into atlas pages, for games
+pub fn export(path: &str, skelements: &Skelements) -> Result<(), String> {
+    let mut atlas = pack_textures(&skelements.textures, &skelements.atlas_options);
+
+    // textures are kept bottom row first for rendering, but images aren't
+    atlas.flip_vertically();
+
+    let mut pages = vec![];
+    for p in &atlas.pages {
+        let img = RgbaImage::from_raw(p.width, p.height, p.rgba.clone())
+            .ok_or("atlas page has the wrong size")?;
+        let mut png = Cursor::new(vec![]);
+        img.write_to(&mut png, ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+        pages.push(png.into_inner());
+    }
+
+    project::write_export(
+        path,
+        &ExportFile {
+            armature: skelements.armature.clone(),
+            regions: atlas.regions,
+            pages,
+        },
+    )
+}
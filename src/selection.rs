@@ -0,0 +1,82 @@
+use crate::mq_backbone::Skelements;
+
+/*
+    `selected_bones` is everything that's selected, and `selected_bone`
+    is the one among them that was picked last. the latter is what
+    windows show and edit, while canvas operations affect all of them
+*/
+
+pub fn is_selected(sk: &Skelements, idx: usize) -> bool {
+    sk.selected_bones.contains(&idx)
+}
+
+/// select only this bone, or nothing if `idx` is `usize::MAX`
+pub fn select(sk: &mut Skelements, idx: usize) {
+    sk.selected_bones.clear();
+    if idx != usize::MAX {
+        sk.selected_bones.push(idx);
+    }
+    sk.selected_bone = idx;
+}
+
+pub fn clear(sk: &mut Skelements) {
+    select(sk, usize::MAX);
+}
+
+/// add a bone to the selection, making it the active one
+pub fn add(sk: &mut Skelements, idx: usize) {
+    if !is_selected(sk, idx) {
+        sk.selected_bones.push(idx);
+    }
+    sk.selected_bone = idx;
+}
+
+/// add a bone to the selection, or remove it if it's already there
+pub fn toggle(sk: &mut Skelements, idx: usize) {
+    if !is_selected(sk, idx) {
+        add(sk, idx);
+        return;
+    }
+    sk.selected_bones.retain(|b| *b != idx);
+    if sk.selected_bone == idx {
+        sk.selected_bone = *sk.selected_bones.last().unwrap_or(&usize::MAX);
+    }
+}
+
+/// drop bones that don't exist anymore, such as after an undo
+pub fn validate(sk: &mut Skelements) {
+    let len = sk.armature.bones.len();
+    sk.selected_bones.retain(|b| *b < len);
+    if sk.selected_bone >= len {
+        sk.selected_bone = *sk.selected_bones.last().unwrap_or(&usize::MAX);
+    }
+}
+
+/// selected bones that don't have a selected ancestor. moving these
+/// is enough to move everything, since children follow their parents
+pub fn roots(sk: &Skelements) -> Vec<usize> {
+    let bones = &sk.armature.bones;
+    let mut roots = vec![];
+    for &idx in &sk.selected_bones {
+        let mut parent_id = bones[idx].parent_id;
+        let mut has_selected_ancestor = false;
+
+        // bounded, in case of cycles
+        for _ in 0..bones.len() {
+            let p = match bones.iter().position(|b| b.id == parent_id) {
+                Some(p) => p,
+                None => break,
+            };
+            if is_selected(sk, p) {
+                has_selected_ancestor = true;
+                break;
+            }
+            parent_id = bones[p].parent_id;
+        }
+
+        if !has_selected_ancestor {
+            roots.push(idx);
+        }
+    }
+    roots
+}
@@ -0,0 +1,101 @@
+use miniquad::RenderingBackend;
+
+use crate::armature_window::find_bone_idx;
+use crate::batch::SpriteBatch;
+use crate::bindings::GpuCache;
+use crate::camera::{view_transform, world_to_screen};
+use crate::gizmo;
+use crate::mq_backbone::{Skelements, Transform2D, Vec2, Vertex};
+use crate::selection;
+use crate::utils::quad_uv_at;
+
+// shape of a bone, relative to its length
+const WIDEST_AT: f32 = 0.15;
+const WIDTH: f32 = 0.08; // on each side
+
+/// outline of a bone pointing along the x axis of `transform`, going around
+/// its corners: base, one side, tip, then the other side
+pub fn bone_shape(transform: &Transform2D, length: f32) -> Vec<Vertex> {
+    #[rustfmt::skip]
+    let corners = [
+        (0., 0.),
+        (WIDEST_AT, WIDTH),
+        (1., 0.),
+        (WIDEST_AT, -WIDTH),
+    ];
+    corners
+        .iter()
+        .map(|(x, y)| Vertex {
+            pos: transform.transform_point(&Vec2 {
+                x: x * length,
+                y: y * length,
+            }),
+            uv: Vec2::default(),
+        })
+        .collect()
+}
+
+/// shapes of every bone, in clip space
+fn shapes(sk: &Skelements) -> Vec<Vec<Vertex>> {
+    let view = view_transform(&sk.camera, &sk.window_size);
+    sk.armature
+        .bones
+        .iter()
+        .zip(&sk.world_transforms)
+        .map(|(b, world)| bone_shape(&view.compose(world), b.length))
+        .collect()
+}
+
+/// index of the topmost bone shape under `p` (in clip space), if any
+pub fn hit(sk: &Skelements, p: &Vec2) -> Option<usize> {
+    if !sk.show_bones {
+        return None;
+    }
+    let shapes = shapes(sk);
+    (0..shapes.len())
+        .rev()
+        .find(|i| sk.armature.bones[*i].length > 0. && quad_uv_at(p, &shapes[*i]).is_some())
+}
+
+/// draw lines from parents to their children, then bones on top of them
+pub fn draw(
+    sk: &Skelements,
+    mq_ctx: &mut Box<dyn RenderingBackend>,
+    cache: &mut GpuCache,
+    batch: &mut SpriteBatch,
+) {
+    let bones = &sk.armature.bones;
+    let transforms = &sk.world_transforms;
+
+    if sk.show_bone_lines {
+        let image = cache.color(mq_ctx, [160, 160, 160, 140]);
+        for (i, b) in bones.iter().enumerate().take(transforms.len()) {
+            let parent = find_bone_idx(bones, b.parent_id);
+            if parent == -1 || parent as usize >= transforms.len() || parent as usize == i {
+                continue;
+            }
+            let screen =
+                |t: &Transform2D| world_to_screen(&t.translation(), &sk.camera, &sk.window_size);
+            let from = screen(&transforms[parent as usize]);
+            let to = screen(&transforms[i]);
+            batch.quad(mq_ctx, image, &gizmo::line(&from, &to, &sk.window_size));
+        }
+    }
+
+    if !sk.show_bones {
+        return;
+    }
+    for (i, verts) in shapes(sk).iter().enumerate() {
+        let color = if i == sk.selected_bone {
+            [255, 200, 90, 230]
+        } else if selection::is_selected(sk, i) {
+            [120, 170, 255, 220]
+        } else if bones[i].id == sk.hovered_bone {
+            [255, 255, 255, 220]
+        } else {
+            [200, 200, 200, 150]
+        };
+        let image = cache.color(mq_ctx, color);
+        batch.quad(mq_ctx, image, verts);
+    }
+}
@@ -0,0 +1,164 @@
+use egui::{Button, ComboBox, Context, ScrollArea};
+use skelform_runtime::armature::{find_skin, skin_attachment};
+
+use crate::mq_backbone::{Bone, Skelements, Skin, SkinAttachment};
+
+/// copy of a bone showing the attachment the current skin gives it
+pub fn skinned(sk: &Skelements, bone: &Bone) -> Bone {
+    let mut b = bone.clone();
+    b.attachment = skin_attachment(&sk.armature.skins, &sk.skin, bone).to_string();
+    b
+}
+
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    // undoing can take away the skin being shown
+    if find_skin(&skelements.armature.skins, &skelements.skin).is_none() {
+        skelements.skin.clear();
+    }
+
+    // edits go to a copy, so the armature is only cloned for undo if it changed
+    let mut skins = skelements.armature.skins.clone();
+    let mut shown = skelements.skin.clone();
+    let mut renamed = false;
+
+    egui::Window::new("Skins")
+        .movable(false)
+        .default_open(false)
+        .show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Shown:");
+                let selected_name = if shown.is_empty() { "Default" } else { &shown };
+                ComboBox::from_id_source("skin")
+                    .selected_text(selected_name.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut shown, String::new(), "Default");
+                        for s in &skins {
+                            ui.selectable_value(&mut shown, s.name.clone(), &s.name);
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("New Skin").clicked() {
+                    let mut n = skins.len();
+                    while find_skin(&skins, &format!("skin{}", n)).is_some() {
+                        n += 1;
+                    }
+                    shown = format!("skin{}", n);
+                    skins.push(Skin {
+                        name: shown.clone(),
+                        ..Default::default()
+                    });
+                }
+                if ui
+                    .add_enabled(!shown.is_empty(), Button::new("Delete Skin"))
+                    .clicked()
+                {
+                    skins.retain(|s| s.name != shown);
+                    for s in &mut skins {
+                        if s.base == shown {
+                            s.base.clear();
+                        }
+                    }
+                    shown.clear();
+                }
+            });
+
+            let idx = match skins.iter().position(|s| s.name == shown) {
+                Some(i) => i,
+                None => {
+                    ui.label("Bones show their own attachments.");
+                    return;
+                }
+            };
+
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                let mut name = shown.clone();
+                let r = ui.text_edit_singleline(&mut name);
+                if r.changed() && !name.is_empty() && find_skin(&skins, &name).is_none() {
+                    for s in &mut skins {
+                        if s.base == shown {
+                            s.base = name.clone();
+                        }
+                    }
+                    skins[idx].name = name.clone();
+                    shown = name;
+                    renamed = true;
+                }
+            });
+
+            // any skin but this one, which bones it leaves out fall back to
+            ui.horizontal(|ui| {
+                ui.label("Base:");
+                let base = &skins[idx].base;
+                let base_name = if base.is_empty() { "Default" } else { base };
+                let mut new_base = base.clone();
+                ComboBox::from_id_source("skin_base")
+                    .selected_text(base_name.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut new_base, String::new(), "Default");
+                        for s in skins.iter().filter(|s| s.name != shown) {
+                            ui.selectable_value(&mut new_base, s.name.clone(), &s.name);
+                        }
+                    });
+                skins[idx].base = new_base;
+            });
+
+            ui.separator();
+            ScrollArea::vertical().max_height(200.).show(ui, |ui| {
+                for bone in &skelements.armature.bones {
+                    if bone.attachments.is_empty() {
+                        continue;
+                    }
+                    draw_bone_attachment(ui, &mut skins[idx], bone);
+                }
+            });
+        });
+
+    skelements.skin = shown;
+    if skins == skelements.armature.skins {
+        return;
+    }
+    let before = skelements.armature.clone();
+    skelements.armature.skins = skins;
+    let after = skelements.armature.clone();
+    skelements.history.edit_armature(before, after, renamed);
+}
+
+/// pick which attachment a bone shows with the skin, or leave it to the base
+fn draw_bone_attachment(ui: &mut egui::Ui, skin: &mut Skin, bone: &Bone) {
+    let entry = skin.attachments.iter().position(|a| a.bone_id == bone.id);
+    let mut value = entry.map(|i| skin.attachments[i].attachment.clone());
+    let selected_name = match &value {
+        None => "(inherit)",
+        Some(a) if a.is_empty() => "(bone texture)",
+        Some(a) => a,
+    }
+    .to_string();
+
+    ui.horizontal(|ui| {
+        ui.label(&bone.name);
+        ComboBox::from_id_source(("skin_bone", bone.id))
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut value, None, "(inherit)");
+                ui.selectable_value(&mut value, Some(String::new()), "(bone texture)");
+                for a in &bone.attachments {
+                    ui.selectable_value(&mut value, Some(a.name.clone()), &a.name);
+                }
+            });
+    });
+
+    match (entry, value) {
+        (Some(i), None) => {
+            skin.attachments.remove(i);
+        }
+        (Some(i), Some(a)) => skin.attachments[i].attachment = a,
+        (None, Some(a)) => skin.attachments.push(SkinAttachment {
+            bone_id: bone.id,
+            attachment: a,
+        }),
+        (None, None) => {}
+    }
+}
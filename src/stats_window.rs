@@ -0,0 +1,21 @@
+use egui::{Align2, Context, Vec2};
+
+use crate::mq_backbone::Skelements;
+
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    if !skelements.show_stats {
+        return;
+    }
+
+    let stats = &skelements.render_stats;
+    egui::Window::new("Stats")
+        .movable(false)
+        .resizable(false)
+        .anchor(Align2::LEFT_BOTTOM, Vec2 { x: 20., y: -10. })
+        .show(egui_ctx, |ui| {
+            ui.label(format!("Draw calls: {}", stats.draw_calls));
+            ui.label(format!("Vertices: {}", stats.vertices));
+            ui.label(format!("Textures: {}", stats.textures));
+            ui.label(format!("Bones: {}", skelements.armature.bones.len()));
+        });
+}
@@ -0,0 +1,335 @@
+use egui::*;
+
+use crate::animation::{self, find_track, key_frames};
+use crate::mq_backbone::{AnimProperty, PlayMode, SelectedKey, Skelements};
+
+// width of a single frame in the timeline
+const FRAME_WIDTH: f32 = 10.;
+const LANE_HEIGHT: f32 = 14.;
+
+pub fn draw(egui_ctx: &Context, skelements: &mut Skelements) {
+    // edits are applied after the window, so the armature
+    // is only cloned for undo when something actually changed
+    let mut new_anim = false;
+    let mut name: Option<String> = None;
+    let mut fps: Option<i32> = None;
+    let (mut key, mut delete_key) = (false, false);
+    let mut move_key: Option<(SelectedKey, i32)> = None;
+    let anim_before = skelements.selected_anim;
+
+    egui::Window::new("Timeline")
+        .movable(false)
+        .anchor(Align2::CENTER_BOTTOM, Vec2 { x: 0., y: -10. })
+        .max_width(400.)
+        .show(egui_ctx, |ui| {
+            let animations = &skelements.armature.animations;
+
+            // animation options
+            ui.horizontal(|ui| {
+                let selected_name = if skelements.selected_anim == usize::MAX {
+                    "None".to_string()
+                } else {
+                    animations[skelements.selected_anim].name.clone()
+                };
+                ComboBox::from_id_source("animation")
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        // none goes back to editing the setup pose
+                        let none =
+                            ui.selectable_value(&mut skelements.selected_anim, usize::MAX, "None");
+                        if none.changed() {
+                            skelements.selected_key = None;
+                            skelements.posed_frame = -1.;
+                        }
+                        for (i, a) in animations.iter().enumerate() {
+                            let r = ui.selectable_value(&mut skelements.selected_anim, i, &a.name);
+                            if r.changed() {
+                                skelements.selected_key = None;
+                                skelements.posed_frame = -1.;
+                            }
+                        }
+                    });
+                if ui.button("New Animation").clicked() {
+                    new_anim = true;
+                }
+            });
+
+            if skelements.selected_anim == usize::MAX {
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                let l = ui.label("Name:");
+                let mut new_name = animations[skelements.selected_anim].name.clone();
+                let r = ui.text_edit_singleline(&mut new_name).labelled_by(l.id);
+                if r.changed() {
+                    name = Some(new_name);
+                }
+            });
+
+            fps = draw_playback(ui, skelements);
+
+            // keyframe options
+            ui.horizontal(|ui| {
+                ui.label("Frame: ".to_string() + &skelements.anim_frame.to_string());
+
+                let can_key = !skelements.selected_bones.is_empty();
+                if ui.add_enabled(can_key, Button::new("Key Bone")).clicked() {
+                    key = true;
+                }
+
+                let can_delete = skelements.selected_key.is_some();
+                if ui.add_enabled(can_delete, Button::new("Delete Key")).clicked() {
+                    delete_key = true;
+                }
+            });
+
+            move_key = draw_lanes(ui, skelements);
+        });
+
+    let edited =
+        new_anim || name.is_some() || fps.is_some() || key || delete_key || move_key.is_some();
+    if edited {
+        let before = skelements.armature.clone();
+        apply_edits(skelements, new_anim, name, fps, key, delete_key, move_key);
+
+        // typing and dragging are ongoing, so they're a single undo
+        let merge = !new_anim && !key && !delete_key;
+        let after = skelements.armature.clone();
+        skelements.history.edit_armature(before, after, merge);
+    }
+
+    // bones are shown as posed by the new animation right away
+    if skelements.selected_anim != anim_before {
+        animation::repose(skelements);
+    }
+}
+
+/// apply what was done in the window, in the same order as it's laid out
+fn apply_edits(
+    skelements: &mut Skelements,
+    new_anim: bool,
+    name: Option<String>,
+    fps: Option<i32>,
+    key: bool,
+    delete_key: bool,
+    move_key: Option<(SelectedKey, i32)>,
+) {
+    let animations = &mut skelements.armature.animations;
+    if new_anim {
+        animation::create_animation(animations);
+        skelements.selected_anim = animations.len() - 1;
+        skelements.selected_key = None;
+        skelements.posed_frame = -1.;
+        return;
+    }
+
+    let anim = &mut animations[skelements.selected_anim];
+    if let Some(name) = name {
+        anim.name = name;
+    }
+    if let Some(fps) = fps {
+        anim.fps = fps;
+    }
+
+    // as posed, since there's an animation
+    if key {
+        for b in &skelements.selected_bones {
+            animation::key_bone(anim, &skelements.posed_bones[*b], skelements.anim_frame);
+        }
+    }
+
+    if let Some(selected) = skelements.selected_key.filter(|_| delete_key) {
+        let track = animation::track_mut(anim, selected.bone_id);
+        animation::remove_key(track, selected.prop, selected.frame);
+        skelements.selected_key = None;
+        skelements.posed_frame = -1.;
+    }
+
+    if let Some((mut selected, to)) = move_key {
+        let track = animation::track_mut(anim, selected.bone_id);
+        if animation::move_key(track, selected.prop, selected.frame, to) {
+            selected.frame = to;
+            skelements.selected_key = Some(selected);
+            skelements.posed_frame = -1.;
+        }
+    }
+}
+
+/// returns the animation's new fps, if it was changed
+fn draw_playback(ui: &mut Ui, skelements: &mut Skelements) -> Option<i32> {
+    #[rustfmt::skip]
+    let modes = [
+        (PlayMode::Once, "Once"),
+        (PlayMode::Loop, "Loop"),
+        (PlayMode::PingPong, "Ping-Pong"),
+    ];
+
+    let mut changed = None;
+    ui.horizontal(|ui| {
+        let play_name = if skelements.playing { "Pause" } else { "Play" };
+        if ui.button(play_name).clicked() {
+            animation::toggle_playing(skelements);
+        }
+
+        for (mode, name) in modes {
+            ui.selectable_value(&mut skelements.play_mode, mode, name);
+        }
+
+        ui.label("FPS:");
+        let mut fps = skelements.armature.animations[skelements.selected_anim].fps;
+        if ui.add(DragValue::new(&mut fps).range(1..=240)).changed() {
+            changed = Some(fps);
+        }
+    });
+
+    // scrub bar
+    let last = animation::last_frame(&skelements.armature.animations[skelements.selected_anim]);
+    let mut frame = skelements.anim_frame;
+    let scrub = ui.add(Slider::new(&mut frame, 0..=last).text("frame"));
+    if scrub.changed() {
+        skelements.playing = false;
+        skelements.anim_frame = frame;
+    }
+    changed
+}
+
+/// returns the selected keyframe and the frame it's being moved to, if it is
+fn draw_lanes(ui: &mut Ui, skelements: &mut Skelements) -> Option<(SelectedKey, i32)> {
+    #[rustfmt::skip]
+    let props = [
+        (AnimProperty::Pos, "pos"),
+        (AnimProperty::Rot, "rot"),
+        (AnimProperty::Scale, "scale"),
+        (AnimProperty::Attachment, "attach"),
+        (AnimProperty::Deform, "deform"),
+    ];
+
+    let anim = &skelements.armature.animations[skelements.selected_anim];
+
+    // always leave some room after the last keyframe to add more
+    let frames = i32::max(animation::last_frame(anim) + 10, 60);
+
+    // only what lanes need of each bone (id, name, and whether it has
+    // attachments or a mesh), since drawing them needs `skelements` mutably
+    let bones: Vec<(i32, String, bool, bool)> = skelements
+        .armature
+        .bones
+        .iter()
+        .map(|b| {
+            let has_mesh = std::iter::once(&b.tex)
+                .chain(b.attachments.iter().map(|a| &a.tex))
+                .any(|t| !t.mesh.triangles.is_empty());
+            (b.id, b.name.clone(), !b.attachments.is_empty(), has_mesh)
+        })
+        .collect();
+
+    let mut moved = None;
+    ScrollArea::both().max_height(150.).show(ui, |ui| {
+        for (id, name, has_attachments, has_mesh) in &bones {
+            let anim = &skelements.armature.animations[skelements.selected_anim];
+            let has_attachment_keys =
+                find_track(anim, *id).is_some_and(|t| !t.attachment.is_empty());
+            let has_deform_keys = find_track(anim, *id).is_some_and(|t| !t.deform.is_empty());
+            for (prop, prop_name) in props {
+                // most bones don't swap images, so leave out the lane for them
+                if prop == AnimProperty::Attachment && !has_attachments && !has_attachment_keys {
+                    continue;
+                }
+                if prop == AnimProperty::Deform && !has_mesh && !has_deform_keys {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    ui.add_sized(
+                        [80., LANE_HEIGHT],
+                        Label::new(format!("{} {}", name, prop_name)),
+                    );
+                    if let Some(m) = draw_lane(ui, skelements, *id, prop, frames) {
+                        moved = Some(m);
+                    }
+                });
+            }
+        }
+    });
+    moved
+}
+
+/// a single row of keyframes, for one property of one bone. returns the
+/// selected keyframe and the frame it's being moved to, if it's in this row
+fn draw_lane(
+    ui: &mut Ui,
+    skelements: &mut Skelements,
+    bone_id: i32,
+    prop: AnimProperty,
+    frames: i32,
+) -> Option<(SelectedKey, i32)> {
+    let (rect, response) = ui.allocate_exact_size(
+        vec2(frames as f32 * FRAME_WIDTH, LANE_HEIGHT),
+        Sense::click_and_drag(),
+    );
+
+    let anim = &skelements.armature.animations[skelements.selected_anim];
+    let mut keys = vec![];
+    if let Some(track) = find_track(anim, bone_id) {
+        keys = key_frames(track, prop);
+    }
+
+    // frame the pointer is on
+    let mut pointer_frame = -1;
+    if let Some(pointer) = response.interact_pointer_pos() {
+        pointer_frame = i32::max(((pointer.x - rect.left()) / FRAME_WIDTH) as i32, 0);
+    }
+
+    // select key on click, or move the playhead if there's none
+    if response.clicked() || response.drag_started() {
+        skelements.playing = false;
+        if keys.contains(&pointer_frame) {
+            skelements.selected_key = Some(SelectedKey {
+                bone_id,
+                prop,
+                frame: pointer_frame,
+            });
+        } else {
+            skelements.selected_key = None;
+        }
+        skelements.anim_frame = pointer_frame;
+    }
+
+    // drag selected key around, onto frames that don't have one yet
+    let mut moved = None;
+    if response.dragged() && pointer_frame != -1 {
+        let in_lane = |k: &SelectedKey| k.bone_id == bone_id && k.prop == prop;
+        if let Some(key) = skelements.selected_key.filter(in_lane) {
+            if key.frame != pointer_frame && !keys.contains(&pointer_frame) {
+                moved = Some((key, pointer_frame));
+            }
+        }
+        skelements.anim_frame = pointer_frame;
+    }
+
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0., Color32::from_rgb(30, 30, 30));
+
+    // playhead
+    let playhead_x = rect.left() + (skelements.anim_frame as f32 + 0.5) * FRAME_WIDTH;
+    painter.vline(
+        playhead_x,
+        rect.y_range(),
+        Stroke::new(1., Color32::from_rgb(200, 60, 60)),
+    );
+
+    for f in keys {
+        let mut col = Color32::from_rgb(150, 150, 150);
+        if let Some(key) = skelements.selected_key {
+            if key.bone_id == bone_id && key.prop == prop && key.frame == f {
+                col = Color32::from_rgb(230, 200, 60);
+            }
+        }
+        let key_rect = Rect::from_min_size(
+            pos2(rect.left() + f as f32 * FRAME_WIDTH, rect.top()),
+            vec2(FRAME_WIDTH, LANE_HEIGHT),
+        );
+        painter.rect_filled(key_rect.shrink(2.), 2., col);
+    }
+    moved
+}
@@ -1,16 +1,165 @@
-use crate::{menu, Context, TopBottomPanel};
+use std::io::Write;
+use std::{fs::File, thread};
 
-pub fn draw(ctx: &Context) {
+use egui::{ComboBox, DragValue, Ui};
+
+use crate::mq_backbone::{Skelements, Vec2};
+use crate::{camera, menu, project, undo, Context, TopBottomPanel};
+
+pub fn draw(ctx: &Context, skelements: &mut Skelements) {
     TopBottomPanel::top("test").show(ctx, |ui| {
         menu::bar(ui, |ui| {
             ui.menu_button("File", |ui| {
                 if ui.button("Open").clicked() {
-                    println!("Open clicked!");
+                    open_project_dialog();
+                    ui.close_menu();
                 }
                 if ui.button("Save").clicked() {
-                    println!("Save clicked!");
+                    save_project_dialog();
+                    ui.close_menu();
+                }
+                if ui.button("Export").clicked() {
+                    export_dialog();
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Edit", |ui| {
+                let can_undo = !skelements.history.undo.is_empty();
+                if ui.add_enabled(can_undo, egui::Button::new("Undo")).clicked() {
+                    undo::undo(skelements);
+                    ui.close_menu();
+                }
+                let can_redo = !skelements.history.redo.is_empty();
+                if ui.add_enabled(can_redo, egui::Button::new("Redo")).clicked() {
+                    undo::redo(skelements);
+                    ui.close_menu();
                 }
             });
+            ui.menu_button("View", |ui| {
+                ui.checkbox(&mut skelements.show_stats, "Render Stats");
+                ui.checkbox(&mut skelements.pixel_hit_test, "Pixel-Perfect Selection");
+                ui.separator();
+                ui.checkbox(&mut skelements.show_sprites, "Sprites");
+                ui.checkbox(&mut skelements.show_bones, "Bones");
+                ui.checkbox(&mut skelements.show_bone_lines, "Parent Lines");
+                ui.separator();
+                draw_camera_options(ui, skelements);
+            });
+            ui.menu_button("Atlas", |ui| {
+                draw_atlas_options(ui, skelements);
+            });
         });
     });
 }
+
+fn open_project_dialog() {
+    thread::spawn(move || {
+        let task = rfd::FileDialog::new()
+            .add_filter("SkelForm Project", &[project::EXTENSION])
+            .pick_file();
+        if task.is_none() {
+            return;
+        }
+        let mut open_path = File::create(".skelform_open_path").unwrap();
+        open_path
+            .write_all(task.unwrap().as_path().to_str().unwrap().as_bytes())
+            .unwrap();
+    });
+}
+
+fn save_project_dialog() {
+    thread::spawn(move || {
+        let task = rfd::FileDialog::new()
+            .add_filter("SkelForm Project", &[project::EXTENSION])
+            .set_file_name("untitled.".to_string() + project::EXTENSION)
+            .save_file();
+        if task.is_none() {
+            return;
+        }
+        let mut save_path = File::create(".skelform_save_path").unwrap();
+        save_path
+            .write_all(task.unwrap().as_path().to_str().unwrap().as_bytes())
+            .unwrap();
+    });
+}
+
+fn export_dialog() {
+    thread::spawn(move || {
+        let task = rfd::FileDialog::new()
+            .add_filter("SkelForm Export", &[project::EXPORT_EXTENSION])
+            .set_file_name("untitled.".to_string() + project::EXPORT_EXTENSION)
+            .save_file();
+        if task.is_none() {
+            return;
+        }
+        let mut export_path = File::create(".skelform_export_path").unwrap();
+        export_path
+            .write_all(task.unwrap().as_path().to_str().unwrap().as_bytes())
+            .unwrap();
+    });
+}
+
+fn draw_camera_options(ui: &mut Ui, skelements: &mut Skelements) {
+    if ui.button("Frame All").clicked() {
+        camera::frame_all(skelements);
+        ui.close_menu();
+    }
+    if ui.button("Frame Selected").clicked() {
+        camera::frame_selected(skelements);
+        ui.close_menu();
+    }
+
+    // presets zoom around the middle of the window
+    let center = Vec2 {
+        x: skelements.window_size.x / 2.,
+        y: skelements.window_size.y / 2.,
+    };
+    let zoom = (skelements.camera.zoom * 100.).round();
+    ui.menu_button(format!("Zoom ({}%)", zoom), |ui| {
+        for preset in camera::ZOOM_PRESETS {
+            if ui.button(format!("{}%", preset * 100.)).clicked() {
+                let window_size = skelements.window_size.clone();
+                camera::zoom_at(&mut skelements.camera, &center, preset, &window_size);
+                ui.close_menu();
+            }
+        }
+    });
+}
+
+fn draw_atlas_options(ui: &mut Ui, skelements: &mut Skelements) {
+    let options = &mut skelements.atlas_options;
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Max size:");
+        ComboBox::from_id_source("atlas_size")
+            .selected_text(options.max_size.to_string())
+            .show_ui(ui, |ui| {
+                for size in [256, 512, 1024, 2048, 4096] {
+                    changed |= ui
+                        .selectable_value(&mut options.max_size, size, size.to_string())
+                        .changed();
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        ui.label("Padding:");
+        changed |= ui
+            .add(DragValue::new(&mut options.padding).range(0..=16))
+            .changed();
+    });
+    changed |= ui
+        .checkbox(&mut options.trim, "Trim transparent borders")
+        .changed();
+
+    if changed {
+        skelements.atlas = None;
+    }
+
+    if let Some(atlas) = &skelements.atlas {
+        ui.separator();
+        for (i, p) in atlas.pages.iter().enumerate() {
+            ui.label(format!("Page {}: {}x{}", i, p.width, p.height));
+        }
+    }
+}
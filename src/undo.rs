@@ -0,0 +1,158 @@
+use crate::animation;
+use crate::mq_backbone::{Armature, Bone, Skelements};
+use crate::selection;
+
+/// a reversible edit, holding the state before and after it
+pub enum Action {
+    /// properties of a single bone changed. boxed, as bones with
+    /// meshes are much bigger than the other actions
+    Bone {
+        idx: usize,
+        before: Box<Bone>,
+        after: Box<Bone>,
+    },
+
+    /// properties of several bones changed at once
+    Bones {
+        idx: Vec<usize>,
+        before: Vec<Bone>,
+        after: Vec<Bone>,
+    },
+
+    /// bones were added, removed or rearranged, or animations changed
+    Armature { before: Armature, after: Armature },
+}
+
+#[derive(Default)]
+pub struct History {
+    pub undo: Vec<Action>,
+    pub redo: Vec<Action>,
+
+    // whether the last action can still be merged with, for
+    // as long as the same interaction (drag, typing) is going on
+    open: bool,
+}
+
+impl History {
+    /// record a change to a bone. if `merge` is set and the last action
+    /// is an ongoing edit of the same bone, it's extended instead
+    pub fn edit_bone(&mut self, idx: usize, before: Bone, after: Bone, merge: bool) {
+        if before == after {
+            return;
+        }
+        if merge && self.open {
+            if let Some(Action::Bone {
+                idx: last_idx,
+                after: last_after,
+                ..
+            }) = self.undo.last_mut()
+            {
+                if *last_idx == idx {
+                    **last_after = after;
+                    return;
+                }
+            }
+        }
+        let (before, after) = (Box::new(before), Box::new(after));
+        self.push(Action::Bone { idx, before, after }, merge);
+    }
+
+    /// record a change to several bones, with `before` and `after`
+    /// in the same order as `idx`. `merge` works the same as in `edit_bone`
+    pub fn edit_bones(
+        &mut self,
+        idx: Vec<usize>,
+        before: Vec<Bone>,
+        after: Vec<Bone>,
+        merge: bool,
+    ) {
+        if before == after {
+            return;
+        }
+        if merge && self.open {
+            if let Some(Action::Bones {
+                idx: last_idx,
+                after: last_after,
+                ..
+            }) = self.undo.last_mut()
+            {
+                if *last_idx == idx {
+                    *last_after = after;
+                    return;
+                }
+            }
+        }
+        self.push(Action::Bones { idx, before, after }, merge);
+    }
+
+    /// record a change to the armature as a whole.
+    /// `merge` works the same as in `edit_bone`
+    pub fn edit_armature(&mut self, before: Armature, after: Armature, merge: bool) {
+        if merge && self.open {
+            if let Some(Action::Armature {
+                after: last_after, ..
+            }) = self.undo.last_mut()
+            {
+                *last_after = after;
+                return;
+            }
+        }
+        self.push(Action::Armature { before, after }, merge);
+    }
+
+    /// stop merging into the last action
+    pub fn end_interaction(&mut self) {
+        self.open = false;
+    }
+
+    fn push(&mut self, action: Action, merge: bool) {
+        self.undo.push(action);
+        self.redo.clear();
+        self.open = merge;
+    }
+}
+
+pub fn undo(sk: &mut Skelements) {
+    if let Some(action) = sk.history.undo.pop() {
+        apply(sk, &action, true);
+        sk.history.redo.push(action);
+    }
+    sk.history.end_interaction();
+}
+
+pub fn redo(sk: &mut Skelements) {
+    if let Some(action) = sk.history.redo.pop() {
+        apply(sk, &action, false);
+        sk.history.undo.push(action);
+    }
+    sk.history.end_interaction();
+}
+
+fn apply(sk: &mut Skelements, action: &Action, reverse: bool) {
+    match action {
+        Action::Bone { idx, before, after } => {
+            let state = if reverse { before } else { after };
+            sk.armature.bones[*idx] = Bone::clone(state);
+        }
+        Action::Bones { idx, before, after } => {
+            let state = if reverse { before } else { after };
+            for (i, b) in idx.iter().zip(state) {
+                sk.armature.bones[*i] = b.clone();
+            }
+        }
+        Action::Armature { before, after } => {
+            let state = if reverse { before } else { after };
+            sk.armature = state.clone();
+
+            // selections might not exist anymore
+            selection::validate(sk);
+            if sk.selected_anim >= sk.armature.animations.len() {
+                sk.selected_anim = usize::MAX;
+            }
+            sk.selected_key = None;
+        }
+    }
+
+    // the setup changed under the pose
+    animation::repose(sk);
+}
@@ -1,36 +1,103 @@
+use skelform_runtime::atlas::{Atlas, AtlasPage};
+
+use crate::bindings::QUAD_INDICES;
 use crate::{mq_backbone::Vertex, Vec2};
 
-/// rotate a point via rotation matrix
-pub fn rotate(point: &Vec2, rot: f32) -> Vec2 {
-    Vec2 {
-        x: point.x * rot.cos() - point.y * rot.sin(),
-        y: point.x * rot.sin() + point.y * rot.cos(),
+// lowest alpha that counts as visible when hit testing pixels
+pub const ALPHA_THRESHOLD: u8 = 16;
+
+/// weights of each corner of a triangle at `p`, or `None` if it's outside.
+/// works with either winding
+pub fn barycentric(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> Option<[f32; 3]> {
+    let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+    if area == 0. {
+        return None;
+    }
+    let wb = ((p.x - a.x) * (c.y - a.y) - (c.x - a.x) * (p.y - a.y)) / area;
+    let wc = ((b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y)) / area;
+    let wa = 1. - wb - wc;
+    if wa < 0. || wb < 0. || wc < 0. {
+        return None;
     }
+    Some([wa, wb, wc])
+}
+
+/// distance from `p` to the closest point on the segment between `a` and `b`,
+/// which can be a single point
+pub fn dist_to_segment(p: &Vec2, a: &Vec2, b: &Vec2) -> f32 {
+    let ab = b.sub(a);
+    let ab_len = ab.dot(&ab);
+    if ab_len == 0. {
+        return p.sub(a).length();
+    }
+    let t = (p.sub(a).dot(&ab) / ab_len).clamp(0., 1.);
+    p.sub(&a.add(&ab.scale(t))).length()
+}
+
+pub fn in_triangle(p: &Vec2, verts: &[Vertex]) -> bool {
+    barycentric(p, &verts[0].pos, &verts[1].pos, &verts[2].pos).is_some()
+}
+
+/// texture coordinates at `p`, if it's inside a quad with vertices going
+/// around its corners. the quad can be rotated, sheared or mirrored
+pub fn quad_uv_at(p: &Vec2, verts: &[Vertex]) -> Option<Vec2> {
+    uv_at(p, verts, &QUAD_INDICES)
 }
 
-pub fn in_bounding_box(point: &Vec2, verts: &Vec<Vertex>, window_size: &Vec2) -> bool {
-    // get the bound based on infinitely-long lines
-    let mut top = -f32::INFINITY;
-    let mut bot = f32::INFINITY;
-    let mut left = f32::INFINITY;
-    let mut right = -f32::INFINITY;
-    for v in verts {
-        left = f32::min(left, v.pos.x);
-        right = f32::max(right, v.pos.x);
-        bot = f32::min(bot, v.pos.y);
-        top = f32::max(top, v.pos.y);
+/// texture coordinates at `p`, if it's inside any of the triangles
+pub fn uv_at(p: &Vec2, verts: &[Vertex], indices: &[u32]) -> Option<Vec2> {
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &verts[tri[i] as usize]);
+        if let Some(w) = barycentric(p, &a.pos, &b.pos, &c.pos) {
+            return Some(Vec2 {
+                x: a.uv.x * w[0] + b.uv.x * w[1] + c.uv.x * w[2],
+                y: a.uv.y * w[0] + b.uv.y * w[1] + c.uv.y * w[2],
+            });
+        }
     }
+    None
+}
 
-    // convert bound positions to screen space
-    let half = Vec2 {
-        x: window_size.x / 2.,
-        y: window_size.y / 2.,
+/// whether any of a sprite's triangles are under `p` (in clip space).
+/// if `pixel_perfect`, only pixels at least somewhat opaque count
+pub fn hits_sprite(
+    p: &Vec2,
+    verts: &[Vertex],
+    indices: &[u32],
+    atlas: &Atlas,
+    tex_idx: usize,
+    pixel_perfect: bool,
+) -> bool {
+    let uv = match uv_at(p, verts, indices) {
+        Some(uv) => uv,
+        None => return false,
     };
-    top = half.y - (half.y * top);
-    bot = half.y - (half.y * bot);
-    left = half.x + (half.x * left);
-    right = half.x + (half.x * right);
+    if !pixel_perfect || tex_idx >= atlas.regions.len() {
+        return true;
+    }
+    let page = &atlas.pages[atlas.regions[tex_idx].page];
+    alpha_at(page, &uv) >= ALPHA_THRESHOLD
+}
 
-    // finally, check if point is inside
-    point.y > top && point.y < bot && point.x > left && point.x < right
+/// alpha of an atlas page at the given texture coordinates
+pub fn alpha_at(page: &AtlasPage, uv: &Vec2) -> u8 {
+    let x = ((uv.x * page.width as f32) as u32).min(page.width - 1);
+    let y = ((uv.y * page.height as f32) as u32).min(page.height - 1);
+    page.rgba[((y * page.width + x) * 4 + 3) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dist_to_segment_handles_single_point() {
+        let a = Vec2 { x: 1., y: 1. };
+        let p = Vec2 { x: 4., y: 5. };
+        assert_eq!(dist_to_segment(&p, &a, &a), 5.);
+
+        let p = Vec2 { x: 2., y: 3. };
+        let b = Vec2 { x: 4., y: 1. };
+        assert_eq!(dist_to_segment(&p, &a, &b), 2.);
+    }
 }